@@ -0,0 +1,117 @@
+use crate::{raw::Hold, Park, RWLock, WaitStrategy};
+use std::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::Arc,
+};
+
+impl<T, W: WaitStrategy> RWLock<T, W> {
+    // like `read`, but the guard keeps the lock alive by itself instead of borrowing it,
+    // so it can be moved into other threads or stored without a lifetime
+    pub fn read_arc(self: &Arc<Self>) -> ArcReadOnlyGuard<T, W> {
+        let hold = self.raw.lock_shared(None);
+        ArcReadOnlyGuard {
+            lock: self.clone(),
+            hold: ManuallyDrop::new(hold.expect("the read lock is acquired without a deadline")),
+        }
+    }
+    pub fn try_read_arc(self: &Arc<Self>) -> Option<ArcReadOnlyGuard<T, W>> {
+        let hold = self.raw.try_lock_shared()?;
+        Some(ArcReadOnlyGuard {
+            lock: self.clone(),
+            hold: ManuallyDrop::new(hold),
+        })
+    }
+    // like `write`, but the guard keeps the lock alive by itself instead of borrowing it
+    pub fn write_arc(self: &Arc<Self>) -> ArcLockGuard<T, W> {
+        let hold = self.raw.lock_exclusive(None);
+        ArcLockGuard {
+            lock: self.clone(),
+            hold: ManuallyDrop::new(hold.expect("the write lock is acquired without a deadline")),
+        }
+    }
+    pub fn try_write_arc(self: &Arc<Self>) -> Option<ArcLockGuard<T, W>> {
+        let hold = self.raw.try_lock_exclusive()?;
+        Some(ArcLockGuard {
+            lock: self.clone(),
+            hold: ManuallyDrop::new(hold),
+        })
+    }
+}
+
+pub struct ArcReadOnlyGuard<T, W: WaitStrategy = Park> {
+    lock: Arc<RWLock<T, W>>,
+    hold: ManuallyDrop<Hold>,
+}
+impl<T, W: WaitStrategy> ArcReadOnlyGuard<T, W> {
+    pub fn lock(&self) -> &Arc<RWLock<T, W>> {
+        &self.lock
+    }
+}
+impl<T, W: WaitStrategy> Deref for ArcReadOnlyGuard<T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+impl<T: fmt::Debug, W: WaitStrategy> fmt::Debug for ArcReadOnlyGuard<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<T, W: WaitStrategy> Drop for ArcReadOnlyGuard<T, W> {
+    fn drop(&mut self) {
+        self.lock
+            .raw
+            .unlock_shared(unsafe { ManuallyDrop::take(&mut self.hold) });
+    }
+}
+
+pub struct ArcLockGuard<T, W: WaitStrategy = Park> {
+    lock: Arc<RWLock<T, W>>,
+    hold: ManuallyDrop<Hold>,
+}
+impl<T, W: WaitStrategy> ArcLockGuard<T, W> {
+    pub fn lock(&self) -> &Arc<RWLock<T, W>> {
+        &self.lock
+    }
+    // turn into a reader without letting another writer in between
+    pub fn downgrade(self) -> ArcReadOnlyGuard<T, W> {
+        // take the `Arc` out without running our `Drop`
+        let mut this = ManuallyDrop::new(self);
+        let lock = unsafe { ptr::read(&this.lock) };
+        let hold = unsafe { ManuallyDrop::take(&mut this.hold) };
+        let hold = lock.raw.downgrade(hold);
+        ArcReadOnlyGuard {
+            lock,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+}
+impl<T, W: WaitStrategy> Deref for ArcLockGuard<T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+impl<T, W: WaitStrategy> DerefMut for ArcLockGuard<T, W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+impl<T: fmt::Debug, W: WaitStrategy> fmt::Debug for ArcLockGuard<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<T, W: WaitStrategy> Drop for ArcLockGuard<T, W> {
+    fn drop(&mut self) {
+        self.lock
+            .raw
+            .unlock_exclusive(unsafe { ManuallyDrop::take(&mut self.hold) });
+    }
+}
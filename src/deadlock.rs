@@ -0,0 +1,64 @@
+#[cfg(not(feature = "deadlock_detection"))]
+use crate::raw::Access;
+
+#[cfg(feature = "deadlock_detection")]
+mod detector;
+
+#[cfg(feature = "deadlock_detection")]
+pub use detector::{set_deadlock_handler, Deadlock};
+#[cfg(feature = "deadlock_detection")]
+pub(crate) use detector::{Detector, Entry};
+
+// tags a lock for the deadlock detection, every lock of a class counts as the same lock
+// when checking the order in which locks are acquired, so that an order taken with some
+// locks is checked against the other locks of the same classes as well, and locks with a
+// level must be acquired in increasing level, nothing is checked unless the
+// `deadlock_detection` feature is enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockClass {
+    name: &'static str,
+    level: Option<u32>,
+}
+impl LockClass {
+    pub const fn new(name: &'static str) -> Self {
+        LockClass { name, level: None }
+    }
+    pub const fn with_level(self, level: u32) -> Self {
+        LockClass {
+            name: self.name,
+            level: Some(level),
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    pub fn level(&self) -> Option<u32> {
+        self.level
+    }
+}
+
+// the deadlock detection state of a single lock, which is nothing at all unless the
+// `deadlock_detection` feature is enabled
+#[cfg(not(feature = "deadlock_detection"))]
+pub(crate) struct Detector;
+
+#[cfg(not(feature = "deadlock_detection"))]
+pub(crate) struct Entry;
+
+#[cfg(not(feature = "deadlock_detection"))]
+impl Detector {
+    pub(crate) const fn new() -> Self {
+        Detector
+    }
+    pub(crate) const fn set_class(&mut self, _: LockClass) {}
+    pub(crate) fn will_block(&self, _: Access, _: crate::Policy) {}
+    pub(crate) fn locked(&self, _: Access) -> Entry {
+        Entry
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn locked_async(&self, _: Access) -> Entry {
+        Entry
+    }
+    pub(crate) fn unlocked(&self, _: Entry) {}
+    pub(crate) fn relocked(&self, _: &Entry, _: Access) {}
+}
@@ -0,0 +1,399 @@
+use super::LockClass;
+use crate::raw::Access;
+use crate::Policy;
+use std::{
+    backtrace::Backtrace,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+// the deadlock detection state of a single lock
+pub(crate) struct Detector {
+    // identifies the lock in the lock-order graph, assigned on its first tracked acquisition
+    // rather than derived from its address, so that moving the lock does not confuse it
+    id: AtomicUsize,
+    // the id shared by the locks of the class, looked up once, 0 until then
+    class_id: AtomicUsize,
+    class: Option<LockClass>,
+}
+
+// a lock acquisition that blocks forever, or that can block forever depending on what the
+// other threads do, reported by the `deadlock_detection` feature
+pub struct Deadlock {
+    reason: String,
+    held: Arc<Backtrace>,
+    acquiring: Backtrace,
+    reversed: Option<Arc<Backtrace>>,
+}
+
+impl Deadlock {
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+    // where the lock that stands in the way was acquired by the current thread, which is
+    // only captured if `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` enables backtraces, as it
+    // would slow down every acquisition otherwise
+    pub fn held_backtrace(&self) -> &Backtrace {
+        &self.held
+    }
+    // where the current thread is trying to acquire the lock
+    pub fn acquiring_backtrace(&self) -> &Backtrace {
+        &self.acquiring
+    }
+    // for a lock-order cycle, where a lock was acquired while holding the one requested now,
+    // which is the opposite order to the current one
+    pub fn reversed_backtrace(&self) -> Option<&Backtrace> {
+        self.reversed.as_deref()
+    }
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadlock detected: {}", self.reason)?;
+        write!(f, "\n\nthe lock held was acquired at:\n{}", self.held)?;
+        if let Some(reversed) = &self.reversed {
+            write!(f, "\n\nthe opposite lock order was taken at:\n{}", reversed)?;
+        }
+        write!(f, "\n\nthe lock is being acquired at:\n{}", self.acquiring)
+    }
+}
+
+impl fmt::Debug for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+type Handler = Arc<dyn Fn(&Deadlock) + Send + Sync>;
+
+// replace the default reaction to a detected deadlock, which is to panic with the report,
+// the acquisition goes ahead as soon as the handler returns
+pub fn set_deadlock_handler<F: Fn(&Deadlock) + Send + Sync + 'static>(handler: F) {
+    *HANDLER.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(handler));
+}
+
+static HANDLER: Mutex<Option<Handler>> = Mutex::new(None);
+
+// the source of the lock ids, 0 stands for a lock that has not been assigned one yet
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+// the ids of the lock classes by name, every lock of a class shares its id in the
+// lock-order graph, while a lock without a class has an id of its own
+static CLASSES: Mutex<Vec<(&'static str, usize)>> = Mutex::new(Vec::new());
+
+// every pair of locks that has been held and acquired in this order by some thread,
+// along with where that happened, a cycle in it is a potential deadlock
+static ORDER: Mutex<Order> = Mutex::new(Order {
+    edges: Vec::new(),
+    reported: Vec::new(),
+});
+
+struct Order {
+    edges: Vec<Edge>,
+    // the cycles that have been reported already, as the pair of locks closing them
+    reported: Vec<(usize, usize)>,
+}
+
+struct Edge {
+    held: usize,
+    acquired: usize,
+    backtrace: Arc<Backtrace>,
+}
+
+struct Held {
+    // tells apart the entries of the guards of the thread
+    token: usize,
+    id: usize,
+    // the id in the lock-order graph
+    node: usize,
+    level: Option<u32>,
+    access: Access,
+    backtrace: Arc<Backtrace>,
+}
+
+// the locks held by a thread, in the order it acquired them, behind a mutex since a guard
+// moved to another thread removes its entry from there
+type HeldList = Arc<Mutex<Vec<Held>>>;
+
+thread_local! {
+    static HELD: HeldList = HeldList::default();
+}
+
+static NEXT_TOKEN: AtomicUsize = AtomicUsize::new(0);
+
+// the entry of a guard in the locks held by the thread that acquired it, which stays
+// with the guard wherever it goes
+pub(crate) struct Entry {
+    held: HeldList,
+    token: usize,
+}
+
+impl Entry {
+    fn update<R>(&self, f: impl FnOnce(&mut Vec<Held>, usize) -> R) -> R {
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = held
+            .iter()
+            .position(|held| held.token == self.token)
+            .expect("a lock is tracked until its guard is released");
+        f(&mut held, index)
+    }
+}
+
+impl Detector {
+    pub(crate) const fn new() -> Self {
+        Detector {
+            id: AtomicUsize::new(0),
+            class_id: AtomicUsize::new(0),
+            class: None,
+        }
+    }
+    pub(crate) const fn set_class(&mut self, class: LockClass) {
+        self.class = Some(class);
+    }
+    fn id(&self) -> usize {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+        let new = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        match self
+            .id
+            .compare_exchange(0, new, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => new,
+            Err(id) => id,
+        }
+    }
+    fn node(&self) -> usize {
+        let Some(class) = self.class else {
+            return self.id();
+        };
+        let node = self.class_id.load(Ordering::Relaxed);
+        if node != 0 {
+            return node;
+        }
+        let mut classes = CLASSES.lock().unwrap_or_else(PoisonError::into_inner);
+        let node = match classes.iter().find(|(name, _)| *name == class.name) {
+            Some(&(_, node)) => node,
+            None => {
+                let node = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+                classes.push((class.name, node));
+                node
+            }
+        };
+        self.class_id.store(node, Ordering::Relaxed);
+        node
+    }
+    fn level(&self) -> Option<u32> {
+        self.class.and_then(|class| class.level)
+    }
+    // called before an acquisition that waits without a deadline
+    pub(crate) fn will_block(&self, access: Access, policy: Policy) {
+        let id = self.id();
+        let deadlock = HELD.with(|held| {
+            let held = held.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(deadlock) = self_deadlock(&held, id, access, policy) {
+                return Some(deadlock);
+            }
+            if let Some(deadlock) = level_deadlock(&held, id, self.level()) {
+                return Some(deadlock);
+            }
+            order_deadlock(&held, id, self.node())
+        });
+        if let Some(deadlock) = deadlock {
+            report(deadlock);
+        }
+    }
+    pub(crate) fn locked(&self, access: Access) -> Entry {
+        HELD.with(|list| self.push(list.clone(), access))
+    }
+    // an async acquisition, whose guard belongs to a task rather than to the thread that
+    // happened to poll it, so it goes in a list of its own and is left out of the checks of
+    // whatever that thread runs next
+    pub(crate) fn locked_async(&self, access: Access) -> Entry {
+        self.push(HeldList::default(), access)
+    }
+    fn push(&self, held: HeldList, access: Access) -> Entry {
+        let access = match access {
+            Access::RecursiveRead => Access::Read,
+            access => access,
+        };
+        let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+        held.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Held {
+                token,
+                id: self.id(),
+                node: self.node(),
+                level: self.level(),
+                access,
+                backtrace: Arc::new(Backtrace::capture()),
+            });
+        Entry { held, token }
+    }
+    // may run on another thread than the one that acquired the lock
+    pub(crate) fn unlocked(&self, entry: Entry) {
+        entry.update(|held, index| held.remove(index));
+    }
+    // the held lock has been upgraded or downgraded
+    pub(crate) fn relocked(&self, entry: &Entry, to: Access) {
+        entry.update(|held, index| held[index].access = to);
+    }
+}
+
+impl Drop for Detector {
+    fn drop(&mut self) {
+        // the lock classes outlive their locks
+        let id = *self.id.get_mut();
+        if id != 0 && self.class.is_none() {
+            let mut order = ORDER.lock().unwrap_or_else(PoisonError::into_inner);
+            order
+                .edges
+                .retain(|edge| edge.held != id && edge.acquired != id);
+            order
+                .reported
+                .retain(|&(held, acquired)| held != id && acquired != id);
+        }
+    }
+}
+
+// an acquisition that waits for a lock the current thread holds itself
+fn self_deadlock(held: &[Held], id: usize, access: Access, policy: Policy) -> Option<Deadlock> {
+    held.iter()
+        .rev()
+        .filter(|held| held.id == id)
+        .find_map(|held| {
+            let reason = match (access, held.access) {
+                (Access::Write, _) => "the write lock is requested by a thread holding the lock",
+                (Access::Upgradable, Access::Upgradable | Access::Write) => {
+                    "the upgradable read lock is requested by a thread holding it or the write lock"
+                }
+                // a writer keeps `UPGRADABLE` while it waits for our read lock to go
+                (Access::Upgradable, Access::Read) => {
+                    "the upgradable read lock is requested by a thread holding a read lock, \
+                 which any waiting writer keeps it from getting"
+                }
+                (Access::Read | Access::RecursiveRead, Access::Write) => {
+                    "the read lock is requested by a thread holding the write lock"
+                }
+                // the read lock queues up behind the waiting writers, which wait for us
+                (Access::Read, Access::Read | Access::Upgradable)
+                    if policy != Policy::ReaderPreferred =>
+                {
+                    "the read lock is requested by a thread holding a read lock, under a policy \
+                 that makes it wait for the writers, unlike `read_recursive`"
+                }
+                (Access::Upgrade, Access::Read) => {
+                    "the upgrade is requested by a thread holding another read lock"
+                }
+                _ => return None,
+            };
+            Some(Deadlock {
+                reason: reason.to_owned(),
+                held: held.backtrace.clone(),
+                acquiring: Backtrace::force_capture(),
+                reversed: None,
+            })
+        })
+}
+
+// an acquisition of a lock whose level is not above the level of a lock the current
+// thread holds, which is reported before any other thread takes the opposite order
+fn level_deadlock(held: &[Held], id: usize, level: Option<u32>) -> Option<Deadlock> {
+    let level = level?;
+    let held = held
+        .iter()
+        .filter(|held| held.id != id)
+        .find(|held| held.level.is_some_and(|held| held >= level))?;
+    Some(Deadlock {
+        reason: format!(
+            "a lock of level {} is requested by a thread holding a lock of level {}",
+            level,
+            held.level.unwrap_or_default(),
+        ),
+        held: held.backtrace.clone(),
+        acquiring: Backtrace::force_capture(),
+        reversed: None,
+    })
+}
+
+// an acquisition that waits for a lock in the opposite order to one taken before, which is
+// reported the first time only, records the order of the current one otherwise, the locks
+// of a single class may be nested in any order
+fn order_deadlock(held: &[Held], id: usize, node: usize) -> Option<Deadlock> {
+    let mut others = held
+        .iter()
+        .filter(|held| held.id != id && held.node != node)
+        .peekable();
+    // the common case of a lock acquired on its own, which has no order to check
+    others.peek()?;
+    let mut order = ORDER.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut backtrace = None;
+    for held in others {
+        if !order.reported.contains(&(held.node, node)) {
+            if let Some(reversed) = find_path(&order.edges, node, held.node) {
+                order.reported.push((held.node, node));
+                return Some(Deadlock {
+                    reason: "the locks are acquired in the opposite order to the one taken before"
+                        .to_owned(),
+                    held: held.backtrace.clone(),
+                    acquiring: Backtrace::force_capture(),
+                    reversed: Some(reversed),
+                });
+            }
+        }
+        if !order
+            .edges
+            .iter()
+            .any(|edge| edge.held == held.node && edge.acquired == node)
+        {
+            let backtrace = backtrace.get_or_insert_with(|| Arc::new(Backtrace::force_capture()));
+            order.edges.push(Edge {
+                held: held.node,
+                acquired: node,
+                backtrace: backtrace.clone(),
+            });
+        }
+    }
+    None
+}
+
+// the backtrace of the first step of a path from `from` to `to` in the lock-order graph
+fn find_path(order: &[Edge], from: usize, to: usize) -> Option<Arc<Backtrace>> {
+    let mut visited = vec![from];
+    let mut pending: Vec<(usize, &Arc<Backtrace>)> = order
+        .iter()
+        .filter(|edge| edge.held == from)
+        .map(|edge| (edge.acquired, &edge.backtrace))
+        .collect();
+    while let Some((id, first)) = pending.pop() {
+        if id == to {
+            return Some(first.clone());
+        }
+        if visited.contains(&id) {
+            continue;
+        }
+        visited.push(id);
+        pending.extend(
+            order
+                .iter()
+                .filter(|edge| edge.held == id)
+                .map(|edge| (edge.acquired, first)),
+        );
+    }
+    None
+}
+
+fn report(deadlock: Deadlock) {
+    let handler = HANDLER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match handler {
+        Some(handler) => handler(&deadlock),
+        None => panic!("{}", deadlock),
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{raw::AsyncWait, LockGuard, Park, RWLock, ReadOnlyGuard, WaitStrategy};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+impl<T, W: WaitStrategy> RWLock<T, W> {
+    // like `read`, but waits by suspending the task instead of blocking the thread,
+    // it does not depend on any particular executor, and its guard belongs to the task
+    // rather than to a thread, so the deadlock detection leaves it out
+    pub fn read_async(&self) -> ReadFuture<'_, T, W> {
+        ReadFuture {
+            lock: self,
+            wait: AsyncWait::default(),
+            done: false,
+        }
+    }
+    // like `write`, but waits by suspending the task instead of blocking the thread, and is
+    // left out of the deadlock detection as well
+    pub fn write_async(&self) -> WriteFuture<'_, T, W> {
+        WriteFuture {
+            lock: self,
+            wait: AsyncWait::default(),
+            done: false,
+        }
+    }
+}
+
+// the future returned by `RWLock::read_async`, dropping it before it completes gives up
+// its place in the queue without acquiring the lock
+pub struct ReadFuture<'a, T, W: WaitStrategy = Park> {
+    lock: &'a RWLock<T, W>,
+    wait: AsyncWait,
+    done: bool,
+}
+impl<'a, T, W: WaitStrategy> Future for ReadFuture<'a, T, W> {
+    type Output = ReadOnlyGuard<'a, T, W>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(!this.done, "`ReadFuture` polled after completion");
+        let Some(hold) = this.lock.raw.poll_lock_shared(&mut this.wait, cx) else {
+            return Poll::Pending;
+        };
+        this.done = true;
+        Poll::Ready(this.lock.read_guard(hold))
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for ReadFuture<'a, T, W> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.lock.raw.cancel_wait(&mut self.wait);
+        }
+    }
+}
+
+// the future returned by `RWLock::write_async`, dropping it before it completes gives up
+// its place in the queue without acquiring the lock
+pub struct WriteFuture<'a, T, W: WaitStrategy = Park> {
+    lock: &'a RWLock<T, W>,
+    wait: AsyncWait,
+    done: bool,
+}
+impl<'a, T, W: WaitStrategy> Future for WriteFuture<'a, T, W> {
+    type Output = LockGuard<'a, T, W>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(!this.done, "`WriteFuture` polled after completion");
+        let Some(hold) = this.lock.raw.poll_lock_exclusive(&mut this.wait, cx) else {
+            return Poll::Pending;
+        };
+        this.done = true;
+        Poll::Ready(this.lock.write_guard(hold))
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for WriteFuture<'a, T, W> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.lock.raw.cancel_wait(&mut self.wait);
+        }
+    }
+}
@@ -1,113 +1,568 @@
-use std::{
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+mod sync;
+
+#[cfg(feature = "std")]
+mod arc;
+mod deadlock;
+#[cfg(feature = "std")]
+mod future;
+#[cfg(feature = "std")]
+mod parking;
+#[cfg(feature = "std")]
+mod poison;
+mod raw;
+mod seqlock;
+#[cfg(feature = "std")]
+mod sharded;
+mod stats;
+mod strategy;
+mod ticket;
+mod trace;
+
+#[cfg(feature = "std")]
+pub use arc::{ArcLockGuard, ArcReadOnlyGuard};
+use core::{
     cell::UnsafeCell,
+    fmt,
+    mem::{self, ManuallyDrop},
     ops::{Deref, DerefMut},
-    sync::atomic::{AtomicI32, AtomicU8, Ordering},
+    ptr,
 };
+pub use deadlock::LockClass;
+#[cfg(feature = "deadlock_detection")]
+pub use deadlock::{set_deadlock_handler, Deadlock};
+#[cfg(feature = "std")]
+pub use future::{ReadFuture, WriteFuture};
+#[cfg(feature = "std")]
+pub use poison::{PoisonLockGuard, PoisonRWLock};
+use raw::{Hold, RawRWLock};
+pub use seqlock::{SeqLock, SeqLockGuard};
+#[cfg(feature = "std")]
+pub use sharded::{ShardedLockGuard, ShardedRWLock, ShardedReadOnlyGuard};
+#[cfg(feature = "stats")]
+pub use stats::{Histogram, LockStats};
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(feature = "std")]
+pub use strategy::{ExponentialBackoff, SpinThenYield};
+pub use strategy::{Park, Spin, WaitStrategy};
+pub use sync::Instant;
+#[cfg(feature = "tracing")]
+pub use trace::{set_long_hold_threshold, set_slow_acquire_threshold};
 
-const IDLE: u8 = 0;
-const READING: u8 = 1;
-const WRITING: u8 = 2;
+// decides who gets the lock when readers and writers are contending for it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // new readers may join as long as the lock is held by readers, a writer has to wait
+    // until there is no reader at all, so a continuous stream of readers starves writers
+    #[default]
+    ReaderPreferred,
+    // new readers back off as soon as a writer is waiting for the lock, so the writer
+    // only has to wait for the readers that are already holding it
+    WriterPreferred,
+    // readers and writers are served in arrival order, consecutive readers still share the
+    // lock, so neither of them can be starved and the wait of each is bounded by those
+    // queued before it, an upgrade keeps the readers arriving after it out as well
+    Fair,
+}
 
-pub struct RWLock<T> {
-    state: AtomicU8,
-    reader: AtomicI32,
+pub struct RWLock<T, W: WaitStrategy = Park> {
+    raw: RawRWLock<W>,
     data: UnsafeCell<T>,
 }
-pub struct ReadOnlyGuard<'a, T> {
+pub struct ReadOnlyGuard<'a, T, W: WaitStrategy = Park> {
     data: &'a T,
-    lock: &'a RWLock<T>,
+    lock: &'a RWLock<T, W>,
+    // handed back to the lock along with the lock itself when the guard goes away
+    hold: ManuallyDrop<Hold>,
+}
+impl<'a, T, W: WaitStrategy> ReadOnlyGuard<'a, T, W> {
+    // narrow the guard down to a part of the protected value
+    pub fn map<U, F>(mut guard: Self, f: F) -> MappedReadOnlyGuard<'a, U, W>
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        let raw = &guard.lock.raw;
+        let data = f(guard.data);
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        MappedReadOnlyGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    // like `map`, but gives the guard back if `f` finds nothing to narrow it down to
+    pub fn try_map<U, F>(mut guard: Self, f: F) -> Result<MappedReadOnlyGuard<'a, U, W>, Self>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        let raw = &guard.lock.raw;
+        let Some(data) = f(guard.data) else {
+            return Err(guard);
+        };
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        Ok(MappedReadOnlyGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        })
+    }
 }
-impl<'a, T> Deref for ReadOnlyGuard<'a, T> {
+impl<'a, T, W: WaitStrategy> Deref for ReadOnlyGuard<'a, T, W> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
         self.data
     }
 }
-impl<'a, T> Drop for ReadOnlyGuard<'a, T> {
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for ReadOnlyGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for ReadOnlyGuard<'a, T, W> {
     fn drop(&mut self) {
-        // the last reader, who is responsible for setting the `state` to `IDLE`
-        if self.lock.reader.fetch_sub(1, Ordering::Relaxed) == 1 {
-            self.lock.state.store(IDLE, Ordering::Release);
-        }
+        self.lock
+            .raw
+            .unlock_shared(unsafe { ManuallyDrop::take(&mut self.hold) });
     }
 }
 
+// the constructors are const, so that a lock can be declared as a `static`
 impl<T> RWLock<T> {
-    pub fn new(val: T) -> Self {
-        RWLock {
-            data: UnsafeCell::new(val),
-            state: AtomicU8::new(IDLE),
-            reader: AtomicI32::new(0),
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            RWLock::with_policy(val, Policy::ReaderPreferred)
         }
     }
-    pub fn read(&self) -> ReadOnlyGuard<'_, T> {
-        // add the count for reader
-        self.reader.fetch_add(1, Ordering::Relaxed);
-        // initially assuming the state is IDLE
-        let mut current = IDLE;
-        // There may be other readers, so the actual `state` is `READING`,
-        // so set `current` to `READING` to try to acquire the read lock
-        // Because the above `fetch_add` races with `fetch_sub` in reader drop,
-        // If the `fetch_sub` in drop of that existed reader wins and set the `state` to `IDLE`,
-        // and `current` here is previously set to `READING`,
-        // the comparsion will fail, so `current` should be set to `IDLE` for the next comparison
-        // another case is that, when `current` is set ot `READING`, the writer instead wins the race
-        // so the current is set to `IDLE` to try to acquire the read lock from releasing of writer
-        while let Err(actual) =
-            self.state
-                .compare_exchange_weak(current, READING, Ordering::Acquire, Ordering::Relaxed)
-        {
-            if actual == IDLE || actual == READING {
-                current = actual;
-            }
-            //assert_ne!(actual,WRITING);
-            if actual == WRITING {
-                current = IDLE;
+    const_fn! {
+        pub fn with_policy(val: T, policy: Policy) -> Self {
+            RWLock::with_policy_and_strategy(val, policy, Park::new())
+        }
+    }
+}
+impl<T, W: WaitStrategy> RWLock<T, W> {
+    const_fn! {
+        pub fn with_strategy(val: T, strategy: W) -> Self {
+            RWLock::with_policy_and_strategy(val, Policy::ReaderPreferred, strategy)
+        }
+    }
+    const_fn! {
+        pub fn with_policy_and_strategy(val: T, policy: Policy, strategy: W) -> Self {
+            RWLock {
+                raw: RawRWLock::new(policy, strategy),
+                data: UnsafeCell::new(val),
             }
-            std::hint::spin_loop();
         }
+    }
+    pub fn policy(&self) -> Policy {
+        self.raw.policy()
+    }
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+    // the exclusive borrow proves that no guard is outstanding, so no locking is needed
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+    // a raw pointer to the protected value, dereferencing it is only sound while the
+    // accesses are synchronized in some other way, e.g. by a guard that is known to be held
+    pub fn data_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+    // tag the lock with a class for the deadlock detection, see `LockClass`
+    pub const fn with_class(mut self, class: LockClass) -> Self {
+        self.raw.set_class(class);
+        self
+    }
+    // name the lock in the events and spans of the `tracing` feature
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.raw.set_name(name);
+        self
+    }
+    // the contention statistics gathered since the lock was created or last reset
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.raw.stats()
+    }
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        self.raw.reset_stats();
+    }
+    pub fn read(&self) -> ReadOnlyGuard<'_, T, W> {
+        let hold = self.raw.lock_shared(None);
+        self.read_guard(hold.expect("the read lock is acquired without a deadline"))
+    }
+    #[cfg(feature = "std")]
+    pub fn try_read_for(&self, timeout: Duration) -> Option<ReadOnlyGuard<'_, T, W>> {
+        // a timeout too large to be represented is as good as no timeout at all
+        self.try_read_until_inner(Instant::now().checked_add(timeout))
+    }
+    #[cfg(feature = "std")]
+    pub fn try_read_until(&self, deadline: Instant) -> Option<ReadOnlyGuard<'_, T, W>> {
+        self.try_read_until_inner(Some(deadline))
+    }
+    #[cfg(feature = "std")]
+    fn try_read_until_inner(&self, deadline: Option<Instant>) -> Option<ReadOnlyGuard<'_, T, W>> {
+        Some(self.read_guard(self.raw.lock_shared(deadline)?))
+    }
+    pub fn try_read(&self) -> Option<ReadOnlyGuard<'_, T, W>> {
+        Some(self.read_guard(self.raw.try_lock_shared()?))
+    }
+    // like `read`, but joins the readers holding the lock even when a writer is waiting for
+    // it, or is queued ahead under `Policy::Fair`, so that a thread already holding a read
+    // guard can take another one without deadlocking against that writer, which `read` may
+    // do under `Policy::WriterPreferred` and `Policy::Fair`, used on its own it lets a
+    // stream of readers starve the writers under any policy
+    pub fn read_recursive(&self) -> ReadOnlyGuard<'_, T, W> {
+        self.read_guard(self.raw.lock_shared_recursive())
+    }
+    pub fn try_read_recursive(&self) -> Option<ReadOnlyGuard<'_, T, W>> {
+        Some(self.read_guard(self.raw.try_lock_shared_recursive()?))
+    }
+    pub fn write(&self) -> LockGuard<'_, T, W> {
+        let hold = self.raw.lock_exclusive(None);
+        self.write_guard(hold.expect("the write lock is acquired without a deadline"))
+    }
+    #[cfg(feature = "std")]
+    pub fn try_write_for(&self, timeout: Duration) -> Option<LockGuard<'_, T, W>> {
+        self.try_write_until_inner(Instant::now().checked_add(timeout))
+    }
+    #[cfg(feature = "std")]
+    pub fn try_write_until(&self, deadline: Instant) -> Option<LockGuard<'_, T, W>> {
+        self.try_write_until_inner(Some(deadline))
+    }
+    #[cfg(feature = "std")]
+    fn try_write_until_inner(&self, deadline: Option<Instant>) -> Option<LockGuard<'_, T, W>> {
+        Some(self.write_guard(self.raw.lock_exclusive(deadline)?))
+    }
+    pub fn try_write(&self) -> Option<LockGuard<'_, T, W>> {
+        Some(self.write_guard(self.raw.try_lock_exclusive()?))
+    }
+    pub fn upgradable_read(&self) -> UpgradableReadGuard<'_, T, W> {
+        self.upgradable_guard(self.raw.lock_upgradable())
+    }
+    pub fn try_upgradable_read(&self) -> Option<UpgradableReadGuard<'_, T, W>> {
+        Some(self.upgradable_guard(self.raw.try_lock_upgradable()?))
+    }
+    // the guards for a lock acquired through `raw`
+    pub(crate) fn read_guard(&self, hold: Hold) -> ReadOnlyGuard<'_, T, W> {
         ReadOnlyGuard {
             data: unsafe { &*self.data.get() },
-            lock: &self,
+            lock: self,
+            hold: ManuallyDrop::new(hold),
         }
     }
-    pub fn write(&self) -> LockGuard<'_, T> {
-        // acquire the lock iif there is no reader
-        while self
-            .state
-            .compare_exchange_weak(IDLE, WRITING, Ordering::Acquire, Ordering::Relaxed)
-            .is_err()
-        {
-            std::hint::spin_loop();
-        }
+    pub(crate) fn write_guard(&self, hold: Hold) -> LockGuard<'_, T, W> {
         LockGuard {
             data: unsafe { &mut *self.data.get() },
-            lock: &self,
+            lock: self,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    fn upgradable_guard(&self, hold: Hold) -> UpgradableReadGuard<'_, T, W> {
+        UpgradableReadGuard {
+            data: unsafe { &*self.data.get() },
+            lock: self,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    pub fn replace(&self, val: T) -> T {
+        mem::replace(&mut *self.write(), val)
+    }
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        mem::take(&mut *self.write())
+    }
+    // exchange the values of two locks, both are locked in the order of their addresses so
+    // that two threads swapping the same pair the other way round cannot deadlock
+    pub fn swap(&self, other: &Self) {
+        if ptr::eq(self, other) {
+            return;
         }
+        let (first, second) = if (self as *const Self) < (other as *const Self) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut first = first.write();
+        let mut second = second.write();
+        mem::swap(&mut *first, &mut *second);
+    }
+}
+impl<T: Default, W: WaitStrategy + Default> Default for RWLock<T, W> {
+    fn default() -> Self {
+        RWLock::with_strategy(T::default(), W::default())
+    }
+}
+impl<T, W: WaitStrategy + Default> From<T> for RWLock<T, W> {
+    fn from(val: T) -> Self {
+        RWLock::with_strategy(val, W::default())
     }
 }
-pub struct LockGuard<'a, T> {
+// never blocks, a lock held by a writer is shown as `<locked>` instead of its value, while
+// the writers that are only waiting for it do not keep the value from being shown, neither
+// does the look count as a read for the deadlock detection, the statistics or the tracing
+impl<T: fmt::Debug, W: WaitStrategy> fmt::Debug for RWLock<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RWLock");
+        match self.raw.try_peek() {
+            Some(_peek) => d.field("data", unsafe { &&*self.data.get() }),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.field("policy", &self.policy()).finish_non_exhaustive()
+    }
+}
+pub struct LockGuard<'a, T, W: WaitStrategy = Park> {
     data: &'a mut T,
-    lock: &'a RWLock<T>,
+    lock: &'a RWLock<T, W>,
+    hold: ManuallyDrop<Hold>,
 }
-impl<'a, T> Deref for LockGuard<'a, T> {
+impl<'a, T, W: WaitStrategy> LockGuard<'a, T, W> {
+    // turn into a reader without letting another writer in between
+    pub fn downgrade(mut self) -> ReadOnlyGuard<'a, T, W> {
+        let lock = self.lock;
+        let hold = unsafe { ManuallyDrop::take(&mut self.hold) };
+        mem::forget(self);
+        lock.read_guard(lock.raw.downgrade(hold))
+    }
+    // narrow the guard down to a part of the protected value
+    pub fn map<U, F>(mut guard: Self, f: F) -> MappedLockGuard<'a, U, W>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let raw = &guard.lock.raw;
+        let data: *mut T = &mut *guard.data;
+        let data = f(unsafe { &mut *data });
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        MappedLockGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    // like `map`, but gives the guard back if `f` finds nothing to narrow it down to
+    pub fn try_map<U, F>(mut guard: Self, f: F) -> Result<MappedLockGuard<'a, U, W>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let raw = &guard.lock.raw;
+        let data: *mut T = &mut *guard.data;
+        let Some(data) = f(unsafe { &mut *data }) else {
+            return Err(guard);
+        };
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        Ok(MappedLockGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        })
+    }
+}
+impl<'a, T, W: WaitStrategy> Deref for LockGuard<'a, T, W> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
         self.data
     }
 }
-impl<'a, T> DerefMut for LockGuard<'a, T> {
+impl<'a, T, W: WaitStrategy> DerefMut for LockGuard<'a, T, W> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.data
     }
 }
-impl<'a, T> Drop for LockGuard<'a, T> {
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for LockGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for LockGuard<'a, T, W> {
+    fn drop(&mut self) {
+        self.lock
+            .raw
+            .unlock_exclusive(unsafe { ManuallyDrop::take(&mut self.hold) });
+    }
+}
+
+// a read lock that can be upgraded to the write lock without letting a writer in between,
+// it coexists with plain readers but excludes writers and other upgradable readers
+pub struct UpgradableReadGuard<'a, T, W: WaitStrategy = Park> {
+    data: &'a T,
+    lock: &'a RWLock<T, W>,
+    hold: ManuallyDrop<Hold>,
+}
+impl<'a, T, W: WaitStrategy> UpgradableReadGuard<'a, T, W> {
+    // wait for the other readers to leave and turn into the writer
+    pub fn upgrade(mut self) -> LockGuard<'a, T, W> {
+        let lock = self.lock;
+        lock.raw.will_upgrade();
+        let hold = unsafe { ManuallyDrop::take(&mut self.hold) };
+        mem::forget(self);
+        lock.write_guard(lock.raw.upgrade(hold))
+    }
+    // turn into the writer only if there is no other reader
+    pub fn try_upgrade(mut self) -> Result<LockGuard<'a, T, W>, Self> {
+        let lock = self.lock;
+        let hold = unsafe { ManuallyDrop::take(&mut self.hold) };
+        mem::forget(self);
+        match lock.raw.try_upgrade(hold) {
+            Ok(hold) => Ok(lock.write_guard(hold)),
+            Err(hold) => Err(lock.upgradable_guard(hold)),
+        }
+    }
+}
+impl<'a, T, W: WaitStrategy> Deref for UpgradableReadGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for UpgradableReadGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for UpgradableReadGuard<'a, T, W> {
+    fn drop(&mut self) {
+        self.lock
+            .raw
+            .unlock_upgradable(unsafe { ManuallyDrop::take(&mut self.hold) });
+    }
+}
+
+// a `ReadOnlyGuard` narrowed down to a part of the protected value, which still releases
+// the whole lock when dropped
+pub struct MappedReadOnlyGuard<'a, T, W: WaitStrategy = Park> {
+    data: &'a T,
+    raw: &'a RawRWLock<W>,
+    hold: ManuallyDrop<Hold>,
+}
+impl<'a, T, W: WaitStrategy> MappedReadOnlyGuard<'a, T, W> {
+    pub fn map<U, F>(mut guard: Self, f: F) -> MappedReadOnlyGuard<'a, U, W>
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        let raw = guard.raw;
+        let data = f(guard.data);
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        MappedReadOnlyGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    pub fn try_map<U, F>(mut guard: Self, f: F) -> Result<MappedReadOnlyGuard<'a, U, W>, Self>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        let raw = guard.raw;
+        let Some(data) = f(guard.data) else {
+            return Err(guard);
+        };
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        Ok(MappedReadOnlyGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        })
+    }
+}
+impl<'a, T, W: WaitStrategy> Deref for MappedReadOnlyGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for MappedReadOnlyGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for MappedReadOnlyGuard<'a, T, W> {
+    fn drop(&mut self) {
+        self.raw
+            .unlock_shared(unsafe { ManuallyDrop::take(&mut self.hold) });
+    }
+}
+
+// a `LockGuard` narrowed down to a part of the protected value, which still releases
+// the whole lock when dropped
+pub struct MappedLockGuard<'a, T, W: WaitStrategy = Park> {
+    data: &'a mut T,
+    raw: &'a RawRWLock<W>,
+    hold: ManuallyDrop<Hold>,
+}
+impl<'a, T, W: WaitStrategy> MappedLockGuard<'a, T, W> {
+    pub fn map<U, F>(mut guard: Self, f: F) -> MappedLockGuard<'a, U, W>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let raw = guard.raw;
+        let data: *mut T = &mut *guard.data;
+        let data = f(unsafe { &mut *data });
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        MappedLockGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        }
+    }
+    pub fn try_map<U, F>(mut guard: Self, f: F) -> Result<MappedLockGuard<'a, U, W>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let raw = guard.raw;
+        let data: *mut T = &mut *guard.data;
+        let Some(data) = f(unsafe { &mut *data }) else {
+            return Err(guard);
+        };
+        let hold = unsafe { ManuallyDrop::take(&mut guard.hold) };
+        mem::forget(guard);
+        Ok(MappedLockGuard {
+            data,
+            raw,
+            hold: ManuallyDrop::new(hold),
+        })
+    }
+}
+impl<'a, T, W: WaitStrategy> Deref for MappedLockGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+impl<'a, T, W: WaitStrategy> DerefMut for MappedLockGuard<'a, T, W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for MappedLockGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for MappedLockGuard<'a, T, W> {
     fn drop(&mut self) {
-        self.lock.state.store(IDLE, Ordering::Release);
+        self.raw
+            .unlock_exclusive(unsafe { ManuallyDrop::take(&mut self.hold) });
     }
 }
 
-unsafe impl<T: Send> Send for RWLock<T> {}
-unsafe impl<T: Sync> Sync for RWLock<T> {}
+// a shared `RWLock` hands out `&mut T` to whichever thread writes, which can move the value
+// out to that thread, so sharing it requires `T: Send` on top of `T: Sync` for the readers
+unsafe impl<T: Send, W: WaitStrategy + Send> Send for RWLock<T, W> {}
+unsafe impl<T: Send + Sync, W: WaitStrategy + Sync> Sync for RWLock<T, W> {}
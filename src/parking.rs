@@ -0,0 +1,128 @@
+use crate::sync::{
+    atomic::{fence, AtomicUsize, Ordering},
+    thread::{self, Thread},
+    Mutex,
+};
+use std::{sync::PoisonError, task::Waker, time::Instant};
+
+pub(crate) struct WaitQueue {
+    // mirrors `threads.len()`, so that releasing an uncontended lock need not take the mutex
+    parked: AtomicUsize,
+    threads: Mutex<Vec<Thread>>,
+}
+impl WaitQueue {
+    const_fn! {
+        pub(crate) fn new() -> Self {
+            WaitQueue {
+                parked: AtomicUsize::new(0),
+                threads: Mutex::new(Vec::new()),
+            }
+        }
+    }
+    // put the current thread to sleep if `should_park` still holds after it has been
+    // registered, so that a release happening in between cannot be missed
+    pub(crate) fn park(&self, should_park: impl FnOnce() -> bool, deadline: Option<Instant>) {
+        let current = thread::current();
+        {
+            let mut threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+            threads.push(current.clone());
+            self.parked.store(threads.len(), Ordering::Relaxed);
+        }
+        // pairs with the fence in `unpark_all`, either the releaser sees us registered
+        // or we see the state it has released
+        fence(Ordering::SeqCst);
+        if should_park() {
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::park_timeout(deadline - now);
+                    }
+                }
+            }
+        }
+        // deregister if nobody has woken us up, which happens on a spurious wakeup,
+        // a timeout, or when `should_park` no longer held
+        let mut threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(index) = threads.iter().position(|t| t.id() == current.id()) {
+            threads.swap_remove(index);
+            self.parked.store(threads.len(), Ordering::Relaxed);
+        }
+    }
+    // wake every parked thread, must be called after the lock state has been released
+    pub(crate) fn unpark_all(&self) {
+        fence(Ordering::SeqCst);
+        if self.parked.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let threads = {
+            let mut threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+            self.parked.store(0, Ordering::Relaxed);
+            std::mem::take(&mut *threads)
+        };
+        for thread in threads {
+            thread.unpark();
+        }
+    }
+}
+
+// the async counterpart of `WaitQueue`, which holds the wakers of the pending lock futures
+pub(crate) struct WakerQueue {
+    // mirrors `wakers.len()`, so that releasing an uncontended lock need not take the mutex
+    pending: AtomicUsize,
+    wakers: Mutex<(u64, Vec<(u64, Waker)>)>,
+}
+impl WakerQueue {
+    const_fn! {
+        pub(crate) fn new() -> Self {
+            WakerQueue {
+                pending: AtomicUsize::new(0),
+                wakers: Mutex::new((0, Vec::new())),
+            }
+        }
+    }
+    // register `waker` under `key`, or under a fresh key stored into `key` if it has none
+    // or it has been woken up since, the caller must retry to acquire the lock afterwards
+    pub(crate) fn register(&self, key: &mut Option<u64>, waker: &Waker) {
+        {
+            let mut guard = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+            let (next_key, wakers) = &mut *guard;
+            match key.and_then(|key| wakers.iter_mut().find(|(k, _)| *k == key)) {
+                Some((_, registered)) => registered.clone_from(waker),
+                None => {
+                    *key = Some(*next_key);
+                    wakers.push((*next_key, waker.clone()));
+                    *next_key = next_key.wrapping_add(1);
+                }
+            }
+            self.pending.store(wakers.len(), Ordering::Relaxed);
+        }
+        // pairs with the fence in `wake_all`, either the releaser sees us registered
+        // or our retry sees the state it has released
+        fence(Ordering::SeqCst);
+    }
+    pub(crate) fn unregister(&self, key: u64) {
+        let mut guard = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        let wakers = &mut guard.1;
+        if let Some(index) = wakers.iter().position(|(k, _)| *k == key) {
+            wakers.swap_remove(index);
+            self.pending.store(wakers.len(), Ordering::Relaxed);
+        }
+    }
+    // wake every pending future, must be called after the lock state has been released
+    pub(crate) fn wake_all(&self) {
+        fence(Ordering::SeqCst);
+        if self.pending.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let wakers = {
+            let mut guard = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+            self.pending.store(0, Ordering::Relaxed);
+            std::mem::take(&mut guard.1)
+        };
+        for (_, waker) in wakers {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    sync::atomic::{AtomicBool, Ordering},
+    LockClass, LockGuard, Park, Policy, RWLock, ReadOnlyGuard, WaitStrategy,
+};
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{LockResult, PoisonError, TryLockError, TryLockResult},
+    thread,
+};
+
+// the std-style flavor of `RWLock`, it is poisoned when a writer panics while holding
+// the lock, after which every acquisition reports that the data may be half-mutated
+pub struct PoisonRWLock<T, W: WaitStrategy = Park> {
+    poisoned: AtomicBool,
+    lock: RWLock<T, W>,
+}
+
+impl<T> PoisonRWLock<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            PoisonRWLock::with_policy(val, Policy::ReaderPreferred)
+        }
+    }
+    const_fn! {
+        pub fn with_policy(val: T, policy: Policy) -> Self {
+            PoisonRWLock::with_policy_and_strategy(val, policy, Park::new())
+        }
+    }
+}
+impl<T, W: WaitStrategy> PoisonRWLock<T, W> {
+    const_fn! {
+        pub fn with_strategy(val: T, strategy: W) -> Self {
+            PoisonRWLock::with_policy_and_strategy(val, Policy::ReaderPreferred, strategy)
+        }
+    }
+    const_fn! {
+        pub fn with_policy_and_strategy(val: T, policy: Policy, strategy: W) -> Self {
+            PoisonRWLock {
+                poisoned: AtomicBool::new(false),
+                lock: RWLock::with_policy_and_strategy(val, policy, strategy),
+            }
+        }
+    }
+    // tag the lock with a class for the deadlock detection, see `LockClass`
+    pub const fn with_class(mut self, class: LockClass) -> Self {
+        self.lock.raw.set_class(class);
+        self
+    }
+    // name the lock in the events and spans of the `tracing` feature
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.lock.raw.set_name(name);
+        self
+    }
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::LockStats {
+        self.lock.stats()
+    }
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        self.lock.reset_stats();
+    }
+    pub fn into_inner(self) -> LockResult<T> {
+        let poisoned = self.is_poisoned();
+        let val = self.lock.into_inner();
+        if poisoned {
+            Err(PoisonError::new(val))
+        } else {
+            Ok(val)
+        }
+    }
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let poisoned = self.is_poisoned();
+        let val = self.lock.get_mut();
+        if poisoned {
+            Err(PoisonError::new(val))
+        } else {
+            Ok(val)
+        }
+    }
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+    // declare the data consistent again, e.g. after a writer has repaired it
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+    pub fn read(&self) -> LockResult<ReadOnlyGuard<'_, T, W>> {
+        self.check(self.lock.read())
+    }
+    pub fn try_read(&self) -> TryLockResult<ReadOnlyGuard<'_, T, W>> {
+        let guard = self.lock.try_read().ok_or(TryLockError::WouldBlock)?;
+        Ok(self.check(guard)?)
+    }
+    pub fn write(&self) -> LockResult<PoisonLockGuard<'_, T, W>> {
+        self.check(self.guard(self.lock.write()))
+    }
+    pub fn try_write(&self) -> TryLockResult<PoisonLockGuard<'_, T, W>> {
+        let guard = self.lock.try_write().ok_or(TryLockError::WouldBlock)?;
+        Ok(self.check(self.guard(guard))?)
+    }
+    fn guard<'a>(&'a self, guard: LockGuard<'a, T, W>) -> PoisonLockGuard<'a, T, W> {
+        PoisonLockGuard {
+            guard,
+            poisoned: &self.poisoned,
+            panicking: thread::panicking(),
+        }
+    }
+    fn check<G>(&self, guard: G) -> LockResult<G> {
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+impl<T: Default, W: WaitStrategy + Default> Default for PoisonRWLock<T, W> {
+    fn default() -> Self {
+        PoisonRWLock::with_strategy(T::default(), W::default())
+    }
+}
+impl<T, W: WaitStrategy + Default> From<T> for PoisonRWLock<T, W> {
+    fn from(val: T) -> Self {
+        PoisonRWLock::with_strategy(val, W::default())
+    }
+}
+impl<T: fmt::Debug, W: WaitStrategy> fmt::Debug for PoisonRWLock<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("PoisonRWLock");
+        match self.lock.raw.try_peek() {
+            Some(_peek) => d.field("data", unsafe { &&*self.lock.data.get() }),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.field("poisoned", &self.is_poisoned())
+            .finish_non_exhaustive()
+    }
+}
+
+// the write guard of `PoisonRWLock`, which poisons the lock if it is dropped by a panic
+pub struct PoisonLockGuard<'a, T, W: WaitStrategy = Park> {
+    guard: LockGuard<'a, T, W>,
+    poisoned: &'a AtomicBool,
+    // a guard acquired while already panicking, e.g. in a `Drop`, does not poison the lock
+    panicking: bool,
+}
+impl<'a, T, W: WaitStrategy> Deref for PoisonLockGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+impl<'a, T, W: WaitStrategy> DerefMut for PoisonLockGuard<'a, T, W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for PoisonLockGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for PoisonLockGuard<'a, T, W> {
+    fn drop(&mut self) {
+        // runs before `guard` releases the lock, so the next owner sees the flag
+        if !self.panicking && thread::panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+    }
+}
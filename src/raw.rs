@@ -0,0 +1,667 @@
+#[cfg(feature = "std")]
+use crate::parking::WakerQueue;
+use crate::sync::{
+    atomic::{AtomicUsize, Ordering},
+    expired, Instant,
+};
+use crate::{
+    deadlock::{self, Detector, LockClass},
+    stats::Stats,
+    ticket::TicketQueue,
+    trace::{self, Tracer},
+    Policy, WaitStrategy,
+};
+#[cfg(any(feature = "stats", feature = "tracing"))]
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::{mem, task::Context};
+
+// the whole lock state is packed into a single word, so that the reader count and the
+// writer can never disagree, from the lowest bit up:
+// - `WRITER`, set while the write lock is held
+// - `UPGRADABLE`, held by the writer or by the upgradable reader, so that at most one of
+//   them exists at a time and an upgrade never races with a writer
+// - the number of writers waiting in `write()` under `Policy::WriterPreferred`, or of
+//   upgrades waiting under `Policy::Fair`, which is not maintained under the other policies
+// - the number of readers holding the lock, including the upgradable reader
+//
+// every acquisition is a successful `Acquire` read-modify-write and every release a `Release`
+// one, so whatever the previous owner did with the data happens before the next owner sees
+// it, the waiting writers are only a hint for the readers and are updated `Relaxed`
+const WRITER: usize = 1;
+const UPGRADABLE: usize = 1 << 1;
+const WAITING_SHIFT: u32 = 2;
+const WAITING_BITS: u32 = usize::BITS / 4;
+const ONE_WAITING: usize = 1 << WAITING_SHIFT;
+const WAITING_MASK: usize = ((1 << WAITING_BITS) - 1) << WAITING_SHIFT;
+const READER_SHIFT: u32 = WAITING_SHIFT + WAITING_BITS;
+const ONE_READER: usize = 1 << READER_SHIFT;
+const MAX_READERS: usize = usize::MAX >> READER_SHIFT;
+
+// the ways a thread may acquire a lock, as far as the deadlock detection and the
+// statistics are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    Read,
+    // a `read_recursive`, which is held as a `Read` afterwards
+    RecursiveRead,
+    Upgradable,
+    // the upgrade of the upgradable read lock held by the thread
+    Upgrade,
+    Write,
+}
+impl Access {
+    #[cfg(any(feature = "stats", feature = "tracing"))]
+    pub(crate) fn is_write(self) -> bool {
+        matches!(self, Access::Write | Access::Upgrade)
+    }
+}
+
+// when an acquisition started, which is only measured for the statistics and the tracing
+#[derive(Clone, Copy)]
+pub(crate) struct Start {
+    #[cfg(any(feature = "stats", feature = "tracing"))]
+    pub(crate) at: Instant,
+}
+impl Start {
+    fn now() -> Self {
+        Start {
+            #[cfg(any(feature = "stats", feature = "tracing"))]
+            at: Instant::now(),
+        }
+    }
+}
+
+// a duration in nanoseconds, saturated to the longest one that fits
+#[cfg(any(feature = "stats", feature = "tracing"))]
+pub(crate) fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+// what the deadlock detection, the statistics and the tracing keep of a guard while it is
+// held, it is carried by the guard rather than by the thread that took it, so that it is at
+// hand wherever the guard is released, and it is nothing at all without those features
+pub(crate) struct Hold {
+    // when the guard started holding the lock in its current mode
+    since: Start,
+    deadlock: deadlock::Entry,
+    // covers the lifetime of the guard, and closes when it is released
+    span: trace::Span,
+}
+
+fn readers(state: usize) -> usize {
+    state >> READER_SHIFT
+}
+
+// the lock itself without the data it protects, so that the guards that no longer know
+// the type of the protected value, such as the mapped ones, can still release it
+pub(crate) struct RawRWLock<W: WaitStrategy> {
+    state: AtomicUsize,
+    // the arrival order of readers and writers, only maintained under `Policy::Fair`
+    tickets: TicketQueue,
+    policy: Policy,
+    strategy: W,
+    // the lock futures waiting for the lock, which are woken up along with the threads
+    #[cfg(feature = "std")]
+    wakers: WakerQueue,
+    deadlock: Detector,
+    stats: Stats,
+    tracer: Tracer,
+}
+
+// the progress of a lock future across its polls
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub(crate) struct AsyncWait {
+    ticket: Option<u32>,
+    waker: Option<u64>,
+    queued_writer: bool,
+    start: Option<Start>,
+}
+impl<W: WaitStrategy> RawRWLock<W> {
+    const_fn! {
+        pub(crate) fn new(policy: Policy, strategy: W) -> Self {
+            RawRWLock {
+                state: AtomicUsize::new(0),
+                tickets: TicketQueue::new(),
+                policy,
+                strategy,
+                #[cfg(feature = "std")]
+                wakers: WakerQueue::new(),
+                deadlock: Detector::new(),
+                stats: Stats::new(),
+                tracer: Tracer::new(),
+            }
+        }
+    }
+    pub(crate) fn policy(&self) -> Policy {
+        self.policy
+    }
+    pub(crate) const fn set_class(&mut self, class: LockClass) {
+        self.deadlock.set_class(class);
+    }
+    pub(crate) const fn set_name(&mut self, name: &'static str) {
+        self.tracer.set_name(name);
+    }
+    #[cfg(feature = "stats")]
+    pub(crate) fn stats(&self) -> crate::LockStats {
+        self.stats.snapshot()
+    }
+    #[cfg(feature = "stats")]
+    pub(crate) fn reset_stats(&self) {
+        self.stats.reset();
+    }
+    // wake up the threads and futures waiting for the lock to change
+    fn notify(&self) {
+        self.strategy.notify();
+        #[cfg(feature = "std")]
+        self.wakers.wake_all();
+    }
+    // wait through the strategy after a failed attempt to acquire the lock for `access`
+    fn wait<F: Fn() -> bool>(
+        &self,
+        access: Access,
+        attempt: &mut u32,
+        should_block: F,
+        deadline: Option<Instant>,
+    ) {
+        self.stats.waited(access);
+        self.strategy.wait(attempt, should_block, deadline);
+    }
+    // keep track of the guards for the deadlock detection, the statistics and the tracing
+    fn locked(&self, access: Access, start: Start) -> Hold {
+        self.hold(access, start, self.deadlock.locked(access))
+    }
+    fn hold(&self, access: Access, start: Start, deadlock: deadlock::Entry) -> Hold {
+        let now = Start::now();
+        self.stats.locked(access, start, now);
+        Hold {
+            since: now,
+            deadlock,
+            span: self.tracer.locked(access, start, now),
+        }
+    }
+    fn unlocked(&self, access: Access, hold: Hold) {
+        self.deadlock.unlocked(hold.deadlock);
+        self.stats.unlocked(access, hold.since);
+        self.tracer.unlocked(access, hold.since, hold.span);
+    }
+    fn relocked(&self, from: Access, to: Access, start: Start, hold: Hold) -> Hold {
+        let now = Start::now();
+        self.deadlock.relocked(&hold.deadlock, to);
+        self.stats.relocked(from, to, start, hold.since, now);
+        Hold {
+            since: now,
+            deadlock: hold.deadlock,
+            span: self
+                .tracer
+                .relocked(from, to, start, hold.since, now, hold.span),
+        }
+    }
+    // returns `None` only if `deadline` has passed before the read lock was acquired
+    pub(crate) fn lock_shared(&self, deadline: Option<Instant>) -> Option<Hold> {
+        self.lock(Access::Read, deadline, || self.acquire_shared(deadline))
+    }
+    fn acquire_shared(&self, deadline: Option<Instant>) -> bool {
+        let mut attempt = 0;
+        while !self.try_acquire_shared() {
+            if expired(deadline) {
+                return false;
+            }
+            // wait until the writer has left, or the waiting writers have had their turn
+            self.wait(
+                Access::Read,
+                &mut attempt,
+                || self.blocks_readers(self.state.load(Ordering::Relaxed)),
+                deadline,
+            );
+        }
+        true
+    }
+    fn blocks_readers(&self, state: usize) -> bool {
+        state & WRITER != 0 || (self.policy != Policy::ReaderPreferred && state & WAITING_MASK != 0)
+    }
+    pub(crate) fn unlock_shared(&self, hold: Hold) {
+        self.unlocked(Access::Read, hold);
+        self.release_shared();
+    }
+    fn release_shared(&self) {
+        // the last reader wakes up the writer waiting for the readers to leave, and the last
+        // but one the upgradable reader that may be waiting to upgrade
+        let state = self.state.fetch_sub(ONE_READER, Ordering::Release);
+        if readers(state) == 1 || (readers(state) == 2 && state & UPGRADABLE != 0) {
+            self.notify();
+        }
+    }
+    pub(crate) fn try_lock_shared(&self) -> Option<Hold> {
+        self.try_lock(Access::Read, || self.try_acquire_shared())
+    }
+    fn try_acquire_shared(&self) -> bool {
+        self.try_add_reader(|state| self.blocks_readers(state))
+    }
+    // acquire the read lock whenever no writer holds it, without queueing up or backing off
+    // for the waiting writers, so a thread already holding the read lock always gets it again
+    pub(crate) fn lock_shared_recursive(&self) -> Hold {
+        self.deadlock.will_block(Access::RecursiveRead, self.policy);
+        let start = Start::now();
+        let mut attempt = 0;
+        while !self.try_acquire_shared_recursive() {
+            // wait until the writer has left
+            self.wait(
+                Access::Read,
+                &mut attempt,
+                || self.state.load(Ordering::Relaxed) & WRITER != 0,
+                None,
+            );
+        }
+        self.locked(Access::RecursiveRead, start)
+    }
+    pub(crate) fn try_lock_shared_recursive(&self) -> Option<Hold> {
+        let start = Start::now();
+        self.try_acquire_shared_recursive()
+            .then(|| self.locked(Access::RecursiveRead, start))
+    }
+    // a read lock for a look at the data that is not an acquisition of its own, such as the
+    // one of `Debug`, which the deadlock detection, the statistics and the tracing ignore
+    pub(crate) fn try_peek(&self) -> Option<Peek<'_, W>> {
+        self.try_acquire_shared_recursive()
+            .then_some(Peek { raw: self })
+    }
+    fn try_acquire_shared_recursive(&self) -> bool {
+        self.try_add_reader(|state| state & WRITER != 0)
+    }
+    // a reader is only ever counted once it holds the lock, so giving up needs no undo
+    fn try_add_reader(&self, blocked: impl Fn(usize) -> bool) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if blocked(state) {
+                return false;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                add_reader(state),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+    // returns `None` only if `deadline` has passed before the write lock was acquired
+    pub(crate) fn lock_exclusive(&self, deadline: Option<Instant>) -> Option<Hold> {
+        self.lock(Access::Write, deadline, || self.acquire_exclusive(deadline))
+    }
+    fn acquire_exclusive(&self, deadline: Option<Instant>) -> bool {
+        // announce the writer, so that no new reader joins the ones holding the lock
+        let queued = self.queue_writer(Access::Write);
+        let acquired = if self.acquire_upgradable(deadline) {
+            let acquired = self.acquire_writing(Access::Write, 0, deadline);
+            if !acquired {
+                self.state.fetch_and(!UPGRADABLE, Ordering::Release);
+            }
+            acquired
+        } else {
+            false
+        };
+        if queued {
+            self.unqueue_writer();
+        }
+        if !acquired {
+            // the readers backing off for us may proceed, as may whoever waits for `UPGRADABLE`
+            self.notify();
+        }
+        acquired
+    }
+    // take the write lock while holding `UPGRADABLE`, once only our own `shares` of the read
+    // lock are left, which are given up in the same step
+    fn acquire_writing(&self, access: Access, shares: usize, deadline: Option<Instant>) -> bool {
+        let mut attempt = 0;
+        while !self.try_acquire_writing(shares) {
+            if expired(deadline) {
+                return false;
+            }
+            // wait until the other readers have left
+            self.wait(
+                access,
+                &mut attempt,
+                || readers(self.state.load(Ordering::Relaxed)) != shares,
+                deadline,
+            );
+        }
+        true
+    }
+    fn acquire_upgradable(&self, deadline: Option<Instant>) -> bool {
+        let mut attempt = 0;
+        while !self.try_acquire_upgradable(false) {
+            if expired(deadline) {
+                return false;
+            }
+            // wait until the writer or the upgradable reader has left
+            self.wait(
+                Access::Write,
+                &mut attempt,
+                || self.state.load(Ordering::Relaxed) & UPGRADABLE != 0,
+                deadline,
+            );
+        }
+        true
+    }
+    // count a waiting writer, returns `false` if it is not counted because the policy does
+    // not need it or the count is full, in which case the writers already counted suffice,
+    // under `Policy::Fair` a writer keeps the readers out by holding the turn while it waits
+    // for them, which an upgrade cannot do, as a writer holding the turn may be waiting for
+    // the upgradable reader
+    fn queue_writer(&self, access: Access) -> bool {
+        let needed = match self.policy {
+            Policy::ReaderPreferred => false,
+            Policy::WriterPreferred => true,
+            Policy::Fair => access == Access::Upgrade,
+        };
+        needed
+            && self
+                .state
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| {
+                    (state & WAITING_MASK != WAITING_MASK).then(|| state + ONE_WAITING)
+                })
+                .is_ok()
+    }
+    fn unqueue_writer(&self) {
+        self.state.fetch_sub(ONE_WAITING, Ordering::Relaxed);
+    }
+    pub(crate) fn try_lock_exclusive(&self) -> Option<Hold> {
+        self.try_lock(Access::Write, || self.try_acquire_exclusive())
+    }
+    // a single step from an unlocked state to the write lock, so that a failed attempt
+    // never holds `UPGRADABLE` in between
+    fn try_acquire_exclusive(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & UPGRADABLE != 0 || readers(state) != 0 {
+                return false;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state | UPGRADABLE | WRITER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+    fn try_acquire_writing(&self, shares: usize) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if readers(state) != shares {
+                return false;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                (state - shares * ONE_READER) | WRITER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+    // take `UPGRADABLE`, along with a share of the read lock for the upgradable reader, who
+    // need not back off for waiting writers since `UPGRADABLE` keeps them out anyway
+    fn try_acquire_upgradable(&self, read: bool) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & UPGRADABLE != 0 {
+                return false;
+            }
+            let new = if read {
+                add_reader(state | UPGRADABLE)
+            } else {
+                state | UPGRADABLE
+            };
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => return true,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+    pub(crate) fn unlock_exclusive(&self, hold: Hold) {
+        self.unlocked(Access::Write, hold);
+        self.state
+            .fetch_and(!(WRITER | UPGRADABLE), Ordering::Release);
+        self.notify();
+    }
+    pub(crate) fn lock_upgradable(&self) -> Hold {
+        let hold = self.lock(Access::Upgradable, None, || self.acquire_upgradable_read());
+        hold.expect("the upgradable read lock is acquired without a deadline")
+    }
+    fn acquire_upgradable_read(&self) -> bool {
+        let mut attempt = 0;
+        while !self.try_acquire_upgradable(true) {
+            // wait until the writer or the upgradable reader has left
+            self.wait(
+                Access::Upgradable,
+                &mut attempt,
+                || self.state.load(Ordering::Relaxed) & UPGRADABLE != 0,
+                None,
+            );
+        }
+        true
+    }
+    pub(crate) fn try_lock_upgradable(&self) -> Option<Hold> {
+        self.try_lock(Access::Upgradable, || self.try_acquire_upgradable(true))
+    }
+    pub(crate) fn unlock_upgradable(&self, hold: Hold) {
+        self.unlocked(Access::Upgradable, hold);
+        // `UPGRADABLE` is set, so subtracting it clears it
+        self.state
+            .fetch_sub(ONE_READER + UPGRADABLE, Ordering::Release);
+        self.notify();
+    }
+    // wait for the other readers to leave while holding the upgradable read lock
+    // the deadlock check of `upgrade`, left to the guard so that it runs while the guard is
+    // still around to release the upgradable read lock if the detector panics
+    pub(crate) fn will_upgrade(&self) {
+        self.deadlock.will_block(Access::Upgrade, self.policy);
+    }
+    pub(crate) fn upgrade(&self, hold: Hold) -> Hold {
+        let start = Start::now();
+        // announce the writer, so that no new reader joins the ones holding the lock
+        let queued = self.queue_writer(Access::Upgrade);
+        self.acquire_writing(Access::Upgrade, 1, None);
+        if queued {
+            self.unqueue_writer();
+        }
+        self.relocked(Access::Upgradable, Access::Write, start, hold)
+    }
+    // gives `hold` back, still holding the upgradable read lock, if there are other readers
+    pub(crate) fn try_upgrade(&self, hold: Hold) -> Result<Hold, Hold> {
+        let start = Start::now();
+        if !self.try_acquire_writing(1) {
+            return Err(hold);
+        }
+        Ok(self.relocked(Access::Upgradable, Access::Write, start, hold))
+    }
+    pub(crate) fn downgrade(&self, hold: Hold) -> Hold {
+        let hold = self.relocked(Access::Write, Access::Read, Start::now(), hold);
+        // turn `WRITER` and `UPGRADABLE`, which are both set, into a share of the read lock
+        // in one step, so that no writer can get in between
+        self.state
+            .fetch_add(ONE_READER - WRITER - UPGRADABLE, Ordering::Release);
+        // the readers waiting for us may join
+        self.notify();
+        hold
+    }
+    // acquire the lock through `acquire`, in turn under `Policy::Fair`, returns `None` only
+    // if `deadline` has passed before the lock was acquired
+    fn lock(
+        &self,
+        access: Access,
+        deadline: Option<Instant>,
+        acquire: impl FnOnce() -> bool,
+    ) -> Option<Hold> {
+        if deadline.is_none() {
+            self.deadlock.will_block(access, self.policy);
+        }
+        let start = Start::now();
+        let acquired = if self.policy != Policy::Fair {
+            acquire()
+        } else if let Some(ticket) = self.wait_for_turn(access, deadline) {
+            let acquired = acquire();
+            // let the readers queued behind us join, or the next writer wait for us to leave,
+            // which it has to do anyway
+            self.pass_turn(ticket);
+            acquired
+        } else {
+            false
+        };
+        acquired.then(|| self.locked(access, start))
+    }
+    // make a single attempt through `try_acquire`, which under `Policy::Fair` only succeeds
+    // if nobody is queued ahead of us
+    fn try_lock(&self, access: Access, try_acquire: impl FnOnce() -> bool) -> Option<Hold> {
+        let start = Start::now();
+        let acquired = if self.policy != Policy::Fair {
+            try_acquire()
+        } else if let Some(ticket) = self.tickets.try_take() {
+            let acquired = try_acquire();
+            self.pass_turn(ticket);
+            acquired
+        } else {
+            false
+        };
+        acquired.then(|| self.locked(access, start))
+    }
+    // queue up under `Policy::Fair`, returns the ticket whose turn has come,
+    // or `None` if `deadline` has passed before that
+    fn wait_for_turn(&self, access: Access, deadline: Option<Instant>) -> Option<u32> {
+        let ticket = self.tickets.take();
+        let mut attempt = 0;
+        while !self.tickets.is_served(ticket) {
+            // without std there is no deadline, and so no giving up
+            #[cfg(feature = "std")]
+            if expired(deadline) {
+                self.tickets.abandon(ticket);
+                // the turn may have been passed on to the waiters behind us
+                self.notify();
+                return None;
+            }
+            self.wait(
+                access,
+                &mut attempt,
+                || !self.tickets.is_served(ticket),
+                deadline,
+            );
+        }
+        Some(ticket)
+    }
+    fn pass_turn(&self, ticket: u32) {
+        self.tickets.pass(ticket);
+        self.notify();
+    }
+    // make progress on an async read acquisition, returns `Some` once the read lock is held
+    #[cfg(feature = "std")]
+    pub(crate) fn poll_lock_shared(
+        &self,
+        wait: &mut AsyncWait,
+        cx: &mut Context<'_>,
+    ) -> Option<Hold> {
+        self.poll_lock(wait, cx, Access::Read, |this| this.try_acquire_shared())
+    }
+    // make progress on an async write acquisition, returns `Some` once the write lock is held
+    #[cfg(feature = "std")]
+    pub(crate) fn poll_lock_exclusive(
+        &self,
+        wait: &mut AsyncWait,
+        cx: &mut Context<'_>,
+    ) -> Option<Hold> {
+        if !wait.queued_writer && self.policy == Policy::WriterPreferred {
+            wait.queued_writer = self.queue_writer(Access::Write);
+        }
+        let hold = self.poll_lock(wait, cx, Access::Write, |this| this.try_acquire_exclusive())?;
+        if mem::take(&mut wait.queued_writer) {
+            self.unqueue_writer();
+        }
+        Some(hold)
+    }
+    #[cfg(feature = "std")]
+    fn poll_lock(
+        &self,
+        wait: &mut AsyncWait,
+        cx: &mut Context<'_>,
+        access: Access,
+        try_acquire: impl Fn(&Self) -> bool,
+    ) -> Option<Hold> {
+        let start = *wait.start.get_or_insert_with(Start::now);
+        if self.policy == Policy::Fair && wait.ticket.is_none() {
+            wait.ticket = Some(self.tickets.take());
+        }
+        let mut registered = false;
+        loop {
+            // under `Policy::Fair` only the holder of the turn may try
+            let acquired = match wait.ticket {
+                Some(ticket) => self.tickets.is_served(ticket) && try_acquire(self),
+                None => try_acquire(self),
+            };
+            if acquired {
+                if let Some(ticket) = wait.ticket.take() {
+                    self.pass_turn(ticket);
+                }
+                if let Some(waker) = wait.waker.take() {
+                    self.wakers.unregister(waker);
+                }
+                // the guard is held by the task, not by the thread polling it
+                let deadlock = self.deadlock.locked_async(access);
+                return Some(self.hold(access, start, deadlock));
+            }
+            if registered {
+                // the task waits for the waker instead of the strategy
+                self.stats.waited(access);
+                return None;
+            }
+            // retry once after registering, the lock may have been released in between
+            self.wakers.register(&mut wait.waker, cx.waker());
+            registered = true;
+        }
+    }
+    // clean up after a lock future that is dropped before it has acquired the lock
+    #[cfg(feature = "std")]
+    pub(crate) fn cancel_wait(&self, wait: &mut AsyncWait) {
+        if let Some(waker) = wait.waker.take() {
+            self.wakers.unregister(waker);
+        }
+        if let Some(ticket) = wait.ticket.take() {
+            if self.tickets.is_served(ticket) {
+                self.pass_turn(ticket);
+            } else {
+                self.tickets.abandon(ticket);
+                self.notify();
+            }
+        }
+        if mem::take(&mut wait.queued_writer) {
+            self.unqueue_writer();
+            // the readers backing off for us may proceed
+            self.notify();
+        }
+    }
+}
+
+// releases the read lock taken by `try_peek`
+pub(crate) struct Peek<'a, W: WaitStrategy> {
+    raw: &'a RawRWLock<W>,
+}
+impl<'a, W: WaitStrategy> Drop for Peek<'a, W> {
+    fn drop(&mut self) {
+        self.raw.release_shared();
+    }
+}
+
+fn add_reader(state: usize) -> usize {
+    assert!(readers(state) < MAX_READERS, "too many readers");
+    state + ONE_READER
+}
@@ -0,0 +1,141 @@
+use crate::{
+    sync::atomic::{fence, AtomicUsize, Ordering},
+    LockGuard, Park, Policy, RWLock, WaitStrategy,
+};
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+// a lock for small `Copy` values whose readers never write to shared memory as long as no
+// writer is around, they copy the value out and check that no writer has been at it in the
+// meantime, the writers go through a plain `RWLock` and bump a sequence number, which is odd
+// while one of them holds the lock, on both ends of their critical section
+pub struct SeqLock<T: Copy, W: WaitStrategy = Park> {
+    seq: AtomicUsize,
+    // only the writers take it, and the readers that find a writer in the way
+    lock: RWLock<T, W>,
+}
+
+impl<T: Copy> SeqLock<T> {
+    const_fn! {
+        pub fn new(val: T) -> Self {
+            SeqLock::with_strategy(val, Park::new())
+        }
+    }
+}
+impl<T: Copy, W: WaitStrategy> SeqLock<T, W> {
+    const_fn! {
+        pub fn with_strategy(val: T, strategy: W) -> Self {
+            SeqLock {
+                seq: AtomicUsize::new(0),
+                lock: RWLock::with_policy_and_strategy(val, Policy::ReaderPreferred, strategy),
+            }
+        }
+    }
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+    pub fn get_mut(&mut self) -> &mut T {
+        self.lock.get_mut()
+    }
+    // retries a read that a writer got in the middle of, and waits for the writer through
+    // the read lock instead of spinning if one is holding the lock already
+    pub fn read(&self) -> T {
+        loop {
+            if let Some(val) = self.try_read() {
+                return val;
+            }
+            if self.seq.load(Ordering::Relaxed) % 2 == 1 {
+                return *self.lock.read();
+            }
+        }
+    }
+    // a single attempt, which fails if a writer holds the lock or gets in the middle of it
+    pub fn try_read(&self) -> Option<T> {
+        let seq = self.seq.load(Ordering::Acquire);
+        if seq % 2 == 1 {
+            return None;
+        }
+        // may race with a writer, in which case the copy is thrown away below, it is only a
+        // `T` once we know it is not torn, a torn one need not even be a valid `T`
+        let val = unsafe { ptr::read_volatile(self.lock.data_ptr() as *const MaybeUninit<T>) };
+        // keeps the copy from being read after the sequence number below
+        fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) != seq {
+            return None;
+        }
+        Some(unsafe { val.assume_init() })
+    }
+    pub fn write(&self) -> SeqLockGuard<'_, T, W> {
+        self.guard(self.lock.write())
+    }
+    pub fn try_write(&self) -> Option<SeqLockGuard<'_, T, W>> {
+        Some(self.guard(self.lock.try_write()?))
+    }
+    fn guard<'a>(&'a self, guard: LockGuard<'a, T, W>) -> SeqLockGuard<'a, T, W> {
+        // only the holder of the write lock changes the sequence number
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        // keeps the writes through the guard from being seen before the odd sequence number
+        fence(Ordering::Release);
+        SeqLockGuard {
+            guard,
+            seq: &self.seq,
+        }
+    }
+}
+impl<T: Copy + Default, W: WaitStrategy + Default> Default for SeqLock<T, W> {
+    fn default() -> Self {
+        SeqLock::with_strategy(T::default(), W::default())
+    }
+}
+impl<T: Copy, W: WaitStrategy + Default> From<T> for SeqLock<T, W> {
+    fn from(val: T) -> Self {
+        SeqLock::with_strategy(val, W::default())
+    }
+}
+// never blocks, the value is shown as `<locked>` while a writer holds the lock or when one
+// gets in the middle of copying it, as it is only ever copied in a single attempt
+impl<T: Copy + fmt::Debug, W: WaitStrategy> fmt::Debug for SeqLock<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SeqLock");
+        match self.try_read() {
+            Some(val) => d.field("data", &val),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish_non_exhaustive()
+    }
+}
+
+// the write guard of `SeqLock`, which makes the sequence number even again when dropped
+pub struct SeqLockGuard<'a, T: Copy, W: WaitStrategy = Park> {
+    guard: LockGuard<'a, T, W>,
+    seq: &'a AtomicUsize,
+}
+impl<'a, T: Copy, W: WaitStrategy> Deref for SeqLockGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+impl<'a, T: Copy, W: WaitStrategy> DerefMut for SeqLockGuard<'a, T, W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+impl<'a, T: Copy + fmt::Debug, W: WaitStrategy> fmt::Debug for SeqLockGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T: Copy, W: WaitStrategy> Drop for SeqLockGuard<'a, T, W> {
+    fn drop(&mut self) {
+        // runs before `guard` releases the lock, so the next writer finds it even
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Release);
+    }
+}
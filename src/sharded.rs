@@ -0,0 +1,262 @@
+use crate::{
+    sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering},
+    Park, WaitStrategy,
+};
+use std::{
+    cell::UnsafeCell,
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    sync::atomic::AtomicUsize as ShardCounter,
+    thread,
+};
+
+// the readers of every lock are spread over this many slots at most
+const MAX_SHARDS: usize = 256;
+
+// hands out the slots to the threads round-robin, in the order in which they first read
+static NEXT_SHARD: ShardCounter = ShardCounter::new(0);
+
+thread_local! {
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+// the reader count of a slot, on a cache line of its own, which is 128 bytes since some
+// CPUs fetch cache lines in pairs
+#[repr(align(128))]
+struct Slot(AtomicUsize);
+
+// a lock for data that is read far more often than it is written, a reader only touches
+// the slot of its thread, so readers on different cores never contend for a cache line,
+// in exchange a writer has to wait for every slot to drain, and the readers back off as
+// soon as a writer shows up
+//
+// a reader announces itself in its slot before looking for the writer, and a writer
+// announces itself before looking at the slots, with a `SeqCst` fence in between on both
+// sides, so of a reader and a writer coming in at the same time at least one sees the other
+pub struct ShardedRWLock<T, W: WaitStrategy = Park> {
+    writer: AtomicBool,
+    slots: Box<[Slot]>,
+    strategy: W,
+    data: UnsafeCell<T>,
+}
+
+impl<T> ShardedRWLock<T> {
+    pub fn new(val: T) -> Self {
+        ShardedRWLock::with_strategy(val, Park::new())
+    }
+}
+impl<T, W: WaitStrategy> ShardedRWLock<T, W> {
+    // one slot for each CPU, rounded up to a power of two so that a thread finds its slot
+    // with a mask
+    pub fn with_strategy(val: T, strategy: W) -> Self {
+        let cpus = thread::available_parallelism().map_or(1, |cpus| cpus.get());
+        let shards = cpus.next_power_of_two().min(MAX_SHARDS);
+        ShardedRWLock {
+            writer: AtomicBool::new(false),
+            slots: (0..shards).map(|_| Slot(AtomicUsize::new(0))).collect(),
+            strategy,
+            data: UnsafeCell::new(val),
+        }
+    }
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+    pub fn read(&self) -> ShardedReadOnlyGuard<'_, T, W> {
+        let shard = self.shard();
+        let mut attempt = 0;
+        while !self.try_lock_shared(shard) {
+            // wait until the writer has left
+            self.strategy
+                .wait(&mut attempt, || self.writer.load(Ordering::Relaxed), None);
+        }
+        ShardedReadOnlyGuard {
+            data: unsafe { &*self.data.get() },
+            lock: self,
+            shard,
+        }
+    }
+    pub fn try_read(&self) -> Option<ShardedReadOnlyGuard<'_, T, W>> {
+        let shard = self.shard();
+        if !self.try_lock_shared(shard) {
+            return None;
+        }
+        Some(ShardedReadOnlyGuard {
+            data: unsafe { &*self.data.get() },
+            lock: self,
+            shard,
+        })
+    }
+    pub fn write(&self) -> ShardedLockGuard<'_, T, W> {
+        let mut attempt = 0;
+        while !self.try_lock_writer() {
+            // wait until the other writer has left
+            self.strategy
+                .wait(&mut attempt, || self.writer.load(Ordering::Relaxed), None);
+        }
+        // no new reader gets in from now on, wait for the ones holding the lock to leave
+        for slot in self.slots.iter() {
+            let mut attempt = 0;
+            while slot.0.load(Ordering::Acquire) != 0 {
+                self.strategy
+                    .wait(&mut attempt, || slot.0.load(Ordering::Relaxed) != 0, None);
+            }
+        }
+        ShardedLockGuard {
+            data: unsafe { &mut *self.data.get() },
+            lock: self,
+        }
+    }
+    pub fn try_write(&self) -> Option<ShardedLockGuard<'_, T, W>> {
+        if !self.try_lock_writer() {
+            return None;
+        }
+        if self
+            .slots
+            .iter()
+            .any(|slot| slot.0.load(Ordering::Acquire) != 0)
+        {
+            self.unlock_exclusive();
+            return None;
+        }
+        Some(ShardedLockGuard {
+            data: unsafe { &mut *self.data.get() },
+            lock: self,
+        })
+    }
+    fn shard(&self) -> usize {
+        SHARD.with(|shard| *shard) & (self.slots.len() - 1)
+    }
+    fn try_lock_shared(&self, shard: usize) -> bool {
+        self.slots[shard].0.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        if !self.writer.load(Ordering::Acquire) {
+            return true;
+        }
+        // back off, the writer may be waiting for this slot to drain
+        self.unlock_shared(shard);
+        false
+    }
+    fn unlock_shared(&self, shard: usize) {
+        self.slots[shard].0.fetch_sub(1, Ordering::Release);
+        // pairs with the fence in `try_lock_writer`, either the writer sees the slot drained
+        // or we see the writer and wake it up
+        fence(Ordering::SeqCst);
+        if self.writer.load(Ordering::Relaxed) {
+            self.strategy.notify();
+        }
+    }
+    fn try_lock_writer(&self) -> bool {
+        if self
+            .writer
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        fence(Ordering::SeqCst);
+        true
+    }
+    fn unlock_exclusive(&self) {
+        self.writer.store(false, Ordering::Release);
+        self.strategy.notify();
+    }
+}
+impl<T: Default, W: WaitStrategy + Default> Default for ShardedRWLock<T, W> {
+    fn default() -> Self {
+        ShardedRWLock::with_strategy(T::default(), W::default())
+    }
+}
+impl<T, W: WaitStrategy + Default> From<T> for ShardedRWLock<T, W> {
+    fn from(val: T) -> Self {
+        ShardedRWLock::with_strategy(val, W::default())
+    }
+}
+// never blocks, the value is shown as `<locked>` as soon as a writer has raised its flag,
+// which it does before waiting for the slots to drain, so a writer still waiting hides it
+// too
+impl<T: fmt::Debug, W: WaitStrategy> fmt::Debug for ShardedRWLock<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ShardedRWLock");
+        match self.try_read() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.field("shards", &self.slots.len()).finish_non_exhaustive()
+    }
+}
+
+// the slots and the writer flag are atomics, so only what the lock hands out matters:
+// `&mut T` to a writer on whichever thread and `&T` to readers on many threads at once
+unsafe impl<T: Send, W: WaitStrategy + Send> Send for ShardedRWLock<T, W> {}
+unsafe impl<T: Send + Sync, W: WaitStrategy + Sync> Sync for ShardedRWLock<T, W> {}
+
+pub struct ShardedReadOnlyGuard<'a, T, W: WaitStrategy = Park> {
+    data: &'a T,
+    lock: &'a ShardedRWLock<T, W>,
+    // the slot the reader is counted in, which is not necessarily the one of the thread
+    // dropping the guard
+    shard: usize,
+}
+impl<'a, T, W: WaitStrategy> Deref for ShardedReadOnlyGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for ShardedReadOnlyGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for ShardedReadOnlyGuard<'a, T, W> {
+    fn drop(&mut self) {
+        self.lock.unlock_shared(self.shard);
+    }
+}
+
+pub struct ShardedLockGuard<'a, T, W: WaitStrategy = Park> {
+    data: &'a mut T,
+    lock: &'a ShardedRWLock<T, W>,
+}
+impl<'a, T, W: WaitStrategy> ShardedLockGuard<'a, T, W> {
+    // count ourselves in the slot of the current thread before lowering the writer flag, so
+    // the lock is never free in between for another writer to take
+    pub fn downgrade(self) -> ShardedReadOnlyGuard<'a, T, W> {
+        let lock = self.lock;
+        mem::forget(self);
+        let shard = lock.shard();
+        lock.slots[shard].0.fetch_add(1, Ordering::Relaxed);
+        lock.unlock_exclusive();
+        ShardedReadOnlyGuard {
+            data: unsafe { &*lock.data.get() },
+            lock,
+            shard,
+        }
+    }
+}
+impl<'a, T, W: WaitStrategy> Deref for ShardedLockGuard<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+impl<'a, T, W: WaitStrategy> DerefMut for ShardedLockGuard<'a, T, W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+impl<'a, T: fmt::Debug, W: WaitStrategy> fmt::Debug for ShardedLockGuard<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+impl<'a, T, W: WaitStrategy> Drop for ShardedLockGuard<'a, T, W> {
+    fn drop(&mut self) {
+        self.lock.unlock_exclusive();
+    }
+}
@@ -0,0 +1,26 @@
+#[cfg(not(feature = "stats"))]
+use crate::raw::{Access, Start};
+
+#[cfg(feature = "stats")]
+mod recorder;
+
+#[cfg(feature = "stats")]
+pub(crate) use recorder::Stats;
+#[cfg(feature = "stats")]
+pub use recorder::{Histogram, LockStats};
+
+// the contention statistics of a single lock, which are nothing at all unless the
+// `stats` feature is enabled
+#[cfg(not(feature = "stats"))]
+pub(crate) struct Stats;
+
+#[cfg(not(feature = "stats"))]
+impl Stats {
+    pub(crate) const fn new() -> Self {
+        Stats
+    }
+    pub(crate) fn waited(&self, _: Access) {}
+    pub(crate) fn locked(&self, _: Access, _: Start, _: Start) {}
+    pub(crate) fn unlocked(&self, _: Access, _: Start) {}
+    pub(crate) fn relocked(&self, _: Access, _: Access, _: Start, _: Start, _: Start) {}
+}
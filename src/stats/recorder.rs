@@ -0,0 +1,205 @@
+use crate::raw::{nanos, Access, Start};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// the number of buckets of a `Histogram`, the first one holds the durations below 1µs,
+// every other one those below twice the bound of the previous one, and the last one
+// everything else
+const BUCKETS: usize = 32;
+
+// the contention statistics of a single lock
+pub(crate) struct Stats {
+    reads: AtomicU64,
+    writes: AtomicU64,
+    upgradable_reads: AtomicU64,
+    upgrades: AtomicU64,
+    read_spins: AtomicU64,
+    write_spins: AtomicU64,
+    read_acquire_times: Buckets,
+    write_acquire_times: Buckets,
+    // in nanoseconds
+    max_read_hold: AtomicU64,
+    max_write_hold: AtomicU64,
+    write_wait: AtomicU64,
+}
+
+struct Buckets([AtomicU64; BUCKETS]);
+
+// a snapshot of the contention statistics of a lock, as returned by `RWLock::stats`,
+// the upgradable reads count as reads and the upgrades as writes where the kinds are
+// not told apart
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct LockStats {
+    // the acquisitions by kind
+    pub reads: u64,
+    pub writes: u64,
+    pub upgradable_reads: u64,
+    pub upgrades: u64,
+    // the number of times an acquisition has waited for the lock through its strategy
+    pub read_spins: u64,
+    pub write_spins: u64,
+    // the time from asking for the lock until getting it
+    pub read_acquire_times: Histogram,
+    pub write_acquire_times: Histogram,
+    // the longest time a single guard held the lock
+    pub max_read_hold: Duration,
+    pub max_write_hold: Duration,
+    // the total time the writers have spent waiting for the lock
+    pub write_wait: Duration,
+}
+
+// the distribution of a duration across buckets with doubling bounds
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    counts: [u64; BUCKETS],
+}
+
+impl Histogram {
+    // the number of durations recorded in total
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+    // the upper bound of each bucket along with the number of durations below it and at
+    // or above the bound of the previous bucket, the bound of the last one is `Duration::MAX`
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.counts.iter().enumerate().map(|(bucket, &count)| {
+            let bound = if bucket == BUCKETS - 1 {
+                Duration::MAX
+            } else {
+                Duration::from_micros(1 << bucket)
+            };
+            (bound, count)
+        })
+    }
+}
+
+impl Buckets {
+    const fn new() -> Self {
+        Buckets([const { AtomicU64::new(0) }; BUCKETS])
+    }
+    fn record(&self, duration: Duration) {
+        let micros = duration.as_micros();
+        let bucket = if micros == 0 {
+            0
+        } else {
+            (u128::BITS - micros.leading_zeros()) as usize
+        };
+        self.0[bucket.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+    fn snapshot(&self) -> Histogram {
+        Histogram {
+            counts: std::array::from_fn(|bucket| self.0[bucket].load(Ordering::Relaxed)),
+        }
+    }
+    fn reset(&self) {
+        for count in &self.0 {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Stats {
+    pub(crate) const fn new() -> Self {
+        Stats {
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            upgradable_reads: AtomicU64::new(0),
+            upgrades: AtomicU64::new(0),
+            read_spins: AtomicU64::new(0),
+            write_spins: AtomicU64::new(0),
+            read_acquire_times: Buckets::new(),
+            write_acquire_times: Buckets::new(),
+            max_read_hold: AtomicU64::new(0),
+            max_write_hold: AtomicU64::new(0),
+            write_wait: AtomicU64::new(0),
+        }
+    }
+    // an acquisition has failed an attempt and waits through the strategy
+    pub(crate) fn waited(&self, access: Access) {
+        let spins = if access.is_write() {
+            &self.write_spins
+        } else {
+            &self.read_spins
+        };
+        spins.fetch_add(1, Ordering::Relaxed);
+    }
+    // `now` is when the lock was acquired
+    pub(crate) fn locked(&self, access: Access, start: Start, now: Start) {
+        let waited = now.at - start.at;
+        let count = match access {
+            Access::Read | Access::RecursiveRead => &self.reads,
+            Access::Upgradable => &self.upgradable_reads,
+            Access::Upgrade => &self.upgrades,
+            Access::Write => &self.writes,
+        };
+        count.fetch_add(1, Ordering::Relaxed);
+        if access.is_write() {
+            self.write_acquire_times.record(waited);
+            self.write_wait.fetch_add(nanos(waited), Ordering::Relaxed);
+        } else {
+            self.read_acquire_times.record(waited);
+        }
+    }
+    // a guard held since `since` has been released
+    pub(crate) fn unlocked(&self, access: Access, since: Start) {
+        let max = if access.is_write() {
+            &self.max_write_hold
+        } else {
+            &self.max_read_hold
+        };
+        max.fetch_max(nanos(since.at.elapsed()), Ordering::Relaxed);
+    }
+    // the held lock has been upgraded or downgraded at `now`, which ends one hold and
+    // starts another
+    pub(crate) fn relocked(
+        &self,
+        from: Access,
+        to: Access,
+        start: Start,
+        since: Start,
+        now: Start,
+    ) {
+        self.unlocked(from, since);
+        // an upgrade counts as an acquisition of its own, a downgrade keeps holding the lock
+        // without waiting
+        if to == Access::Write {
+            self.locked(Access::Upgrade, start, now);
+        }
+    }
+    pub(crate) fn snapshot(&self) -> LockStats {
+        let load = |count: &AtomicU64| count.load(Ordering::Relaxed);
+        LockStats {
+            reads: load(&self.reads),
+            writes: load(&self.writes),
+            upgradable_reads: load(&self.upgradable_reads),
+            upgrades: load(&self.upgrades),
+            read_spins: load(&self.read_spins),
+            write_spins: load(&self.write_spins),
+            read_acquire_times: self.read_acquire_times.snapshot(),
+            write_acquire_times: self.write_acquire_times.snapshot(),
+            max_read_hold: Duration::from_nanos(load(&self.max_read_hold)),
+            max_write_hold: Duration::from_nanos(load(&self.max_write_hold)),
+            write_wait: Duration::from_nanos(load(&self.write_wait)),
+        }
+    }
+    pub(crate) fn reset(&self) {
+        for count in [
+            &self.reads,
+            &self.writes,
+            &self.upgradable_reads,
+            &self.upgrades,
+            &self.read_spins,
+            &self.write_spins,
+            &self.max_read_hold,
+            &self.max_write_hold,
+            &self.write_wait,
+        ] {
+            count.store(0, Ordering::Relaxed);
+        }
+        self.read_acquire_times.reset();
+        self.write_acquire_times.reset();
+    }
+}
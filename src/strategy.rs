@@ -0,0 +1,157 @@
+use crate::sync::{hint, Instant};
+#[cfg(feature = "std")]
+use crate::{parking::WaitQueue, sync::thread};
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+// the number of rounds spent in `spin_loop` (doubling each round) and then in
+// `yield_now` before `SpinThenYield` only yields and `Park` puts a waiter to sleep
+#[cfg(feature = "std")]
+const SPIN_ROUNDS: u32 = 10;
+#[cfg(feature = "std")]
+const YIELD_ROUNDS: u32 = 20;
+
+// the bounds of `ExponentialBackoff`, which sleeps once spinning has reached its limit
+#[cfg(feature = "std")]
+const BACKOFF_SPIN_ROUNDS: u32 = 6;
+#[cfg(feature = "std")]
+const BACKOFF_MIN_SLEEP: Duration = Duration::from_micros(1);
+#[cfg(feature = "std")]
+const BACKOFF_MAX_SLEEP: Duration = Duration::from_millis(1);
+
+pub trait WaitStrategy {
+    // called by an acquisition after each failed attempt, `attempt` counts the failed
+    // attempts so far and may be reset by the strategy, `should_block` tells whether
+    // the lock is still held in a way that blocks the acquisition, and the strategy
+    // must not wait past `deadline`
+    fn wait<F: Fn() -> bool>(&self, attempt: &mut u32, should_block: F, deadline: Option<Instant>);
+    // called every time the lock has been released and waiters may proceed
+    fn notify(&self);
+}
+
+#[cfg(feature = "std")]
+fn spin(rounds: u32) {
+    for _ in 0..1u32 << rounds {
+        hint::spin_loop();
+    }
+}
+
+// busy-waits with `spin_loop` only, for threads that must never give up their core
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spin;
+impl Spin {
+    pub const fn new() -> Self {
+        Spin
+    }
+}
+impl WaitStrategy for Spin {
+    fn wait<F: Fn() -> bool>(&self, _: &mut u32, _: F, _: Option<Instant>) {
+        hint::spin_loop();
+    }
+    fn notify(&self) {}
+}
+
+// spins with a doubling number of iterations for a while, then yields the thread
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpinThenYield;
+#[cfg(feature = "std")]
+impl SpinThenYield {
+    pub const fn new() -> Self {
+        SpinThenYield
+    }
+}
+#[cfg(feature = "std")]
+impl WaitStrategy for SpinThenYield {
+    fn wait<F: Fn() -> bool>(&self, attempt: &mut u32, _: F, _: Option<Instant>) {
+        if *attempt < SPIN_ROUNDS {
+            spin(*attempt);
+            *attempt += 1;
+        } else {
+            thread::yield_now();
+        }
+    }
+    fn notify(&self) {}
+}
+
+// spins with a doubling number of iterations, then sleeps for a doubling duration
+// capped at `BACKOFF_MAX_SLEEP`, which keeps the CPU free on shared machines
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExponentialBackoff;
+#[cfg(feature = "std")]
+impl ExponentialBackoff {
+    pub const fn new() -> Self {
+        ExponentialBackoff
+    }
+}
+#[cfg(feature = "std")]
+impl WaitStrategy for ExponentialBackoff {
+    fn wait<F: Fn() -> bool>(&self, attempt: &mut u32, _: F, deadline: Option<Instant>) {
+        if *attempt < BACKOFF_SPIN_ROUNDS {
+            spin(*attempt);
+            *attempt += 1;
+            return;
+        }
+        let mut sleep = BACKOFF_MIN_SLEEP * (1 << (*attempt - BACKOFF_SPIN_ROUNDS));
+        if sleep < BACKOFF_MAX_SLEEP {
+            *attempt += 1;
+        } else {
+            sleep = BACKOFF_MAX_SLEEP;
+        }
+        if let Some(deadline) = deadline {
+            sleep = sleep.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        thread::sleep(sleep);
+    }
+    fn notify(&self) {}
+}
+
+// spins and yields for a while, then parks the thread until the lock is released,
+// this is the default strategy of `RWLock`, without std there is no thread to yield or
+// park and it only spins like `Spin`
+pub struct Park {
+    #[cfg(feature = "std")]
+    waiters: WaitQueue,
+}
+impl Park {
+    const_fn! {
+        pub fn new() -> Self {
+            Park {
+                #[cfg(feature = "std")]
+                waiters: WaitQueue::new(),
+            }
+        }
+    }
+}
+impl Default for Park {
+    fn default() -> Self {
+        Park::new()
+    }
+}
+#[cfg(feature = "std")]
+impl WaitStrategy for Park {
+    fn wait<F: Fn() -> bool>(&self, attempt: &mut u32, should_block: F, deadline: Option<Instant>) {
+        if *attempt < SPIN_ROUNDS {
+            spin(*attempt);
+        } else if *attempt < SPIN_ROUNDS + YIELD_ROUNDS {
+            thread::yield_now();
+        } else {
+            self.waiters.park(should_block, deadline);
+            // spin again for a while after being woken up
+            *attempt = 0;
+            return;
+        }
+        *attempt += 1;
+    }
+    fn notify(&self) {
+        self.waiters.unpark_all();
+    }
+}
+#[cfg(not(feature = "std"))]
+impl WaitStrategy for Park {
+    fn wait<F: Fn() -> bool>(&self, _: &mut u32, _: F, _: Option<Instant>) {
+        hint::spin_loop();
+    }
+    fn notify(&self) {}
+}
@@ -0,0 +1,61 @@
+// the synchronization primitives used throughout the crate, they are the ones of `loom`
+// when built with `--cfg loom`, so that the lock can be model-checked by tests/loom.rs,
+// and only those of `core` are available without the `std` feature
+
+#[cfg(not(loom))]
+pub(crate) use core::{hint, sync::atomic};
+#[cfg(loom)]
+pub(crate) use loom::{
+    hint,
+    sync::{atomic, Mutex},
+};
+#[cfg(all(not(loom), feature = "std"))]
+pub(crate) use std::{sync::Mutex, thread};
+
+// re-exported as `rwlock::Instant` with or without std, so that a `WaitStrategy` can name
+// it either way
+#[cfg(feature = "std")]
+pub use std::time::Instant;
+
+// without std there is no clock, so no acquisition has a deadline and `WaitStrategy::wait`
+// is always given `None`
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Instant {}
+
+// whether `deadline` has passed
+#[cfg(feature = "std")]
+pub(crate) fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+#[cfg(not(feature = "std"))]
+pub(crate) fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| match deadline {})
+}
+
+#[cfg(loom)]
+pub(crate) mod thread {
+    pub(crate) use loom::thread::{current, park, yield_now, Thread};
+    use std::time::Duration;
+
+    // loom does not model time, so waiting for a while becomes a yield,
+    // the callers are prepared for waking up early anyway
+    pub(crate) fn park_timeout(_: Duration) {
+        yield_now();
+    }
+    pub(crate) fn sleep(_: Duration) {
+        yield_now();
+    }
+}
+
+// declares a `const fn`, except under loom whose primitives cannot be created in const context
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $name:ident($($args:tt)*) -> $ret:ty $body:block) => {
+        $(#[$attr])*
+        #[cfg(not(loom))]
+        $vis const fn $name($($args)*) -> $ret $body
+        $(#[$attr])*
+        #[cfg(loom)]
+        $vis fn $name($($args)*) -> $ret $body
+    };
+}
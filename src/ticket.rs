@@ -0,0 +1,100 @@
+use crate::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "std")]
+use crate::sync::{
+    atomic::{fence, AtomicUsize},
+    Mutex,
+};
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
+
+// hands out turns in arrival order, the holder of the turn is the only one allowed to
+// acquire the underlying lock and passes the turn on right after it
+pub(crate) struct TicketQueue {
+    next: AtomicU32,
+    serving: AtomicU32,
+    // the tickets whose holders gave up before their turn came, they are skipped when the
+    // turn is passed on, `abandoned_len` mirrors the length to keep the common path lock-free,
+    // without std nobody can give up, as there are neither deadlines nor lock futures
+    #[cfg(feature = "std")]
+    abandoned_len: AtomicUsize,
+    #[cfg(feature = "std")]
+    abandoned: Mutex<Vec<u32>>,
+}
+impl TicketQueue {
+    const_fn! {
+        pub(crate) fn new() -> Self {
+            TicketQueue {
+                next: AtomicU32::new(0),
+                serving: AtomicU32::new(0),
+                #[cfg(feature = "std")]
+                abandoned_len: AtomicUsize::new(0),
+                #[cfg(feature = "std")]
+                abandoned: Mutex::new(Vec::new()),
+            }
+        }
+    }
+    pub(crate) fn take(&self) -> u32 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+    // take a ticket only if it is served immediately, i.e. nobody is queued
+    pub(crate) fn try_take(&self) -> Option<u32> {
+        let serving = self.serving.load(Ordering::Acquire);
+        self.next
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .ok()
+    }
+    pub(crate) fn is_served(&self, ticket: u32) -> bool {
+        self.serving.load(Ordering::Acquire) == ticket
+    }
+    // pass the turn of `ticket`, which must be served, on to the next ticket
+    #[cfg(feature = "std")]
+    pub(crate) fn pass(&self, ticket: u32) {
+        self.serving.store(ticket.wrapping_add(1), Ordering::SeqCst);
+        // pairs with the fence in `abandon`, either we see the abandoned ticket or
+        // its holder sees that its turn has come
+        fence(Ordering::SeqCst);
+        if self.abandoned_len.load(Ordering::Relaxed) > 0 {
+            let mut abandoned = self
+                .abandoned
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.skip_abandoned(&mut abandoned);
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn pass(&self, ticket: u32) {
+        self.serving
+            .store(ticket.wrapping_add(1), Ordering::Release);
+    }
+    // give up `ticket` before it is served
+    #[cfg(feature = "std")]
+    pub(crate) fn abandon(&self, ticket: u32) {
+        let mut abandoned = self
+            .abandoned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        abandoned.push(ticket);
+        self.abandoned_len.store(abandoned.len(), Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        // the turn may have come in the meantime, and then nobody else is going to pass it on
+        self.skip_abandoned(&mut abandoned);
+    }
+    #[cfg(feature = "std")]
+    fn skip_abandoned(&self, abandoned: &mut Vec<u32>) {
+        loop {
+            let serving = self.serving.load(Ordering::Acquire);
+            let Some(index) = abandoned.iter().position(|&ticket| ticket == serving) else {
+                break;
+            };
+            abandoned.swap_remove(index);
+            self.serving
+                .store(serving.wrapping_add(1), Ordering::Release);
+        }
+        self.abandoned_len.store(abandoned.len(), Ordering::Relaxed);
+    }
+}
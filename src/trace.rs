@@ -0,0 +1,43 @@
+#[cfg(not(feature = "tracing"))]
+use crate::raw::{Access, Start};
+
+#[cfg(feature = "tracing")]
+mod tracer;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracer::Tracer;
+#[cfg(feature = "tracing")]
+pub use tracer::{set_long_hold_threshold, set_slow_acquire_threshold};
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+// the `tracing` instrumentation of a single lock, which is nothing at all unless the
+// `tracing` feature is enabled
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Tracer;
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Tracer {
+    pub(crate) const fn new() -> Self {
+        Tracer
+    }
+    pub(crate) const fn set_name(&mut self, _: &'static str) {}
+    pub(crate) fn locked(&self, _: Access, _: Start, _: Start) -> Span {
+        Span
+    }
+    pub(crate) fn unlocked(&self, _: Access, _: Start, _: Span) {}
+    pub(crate) fn relocked(
+        &self,
+        _: Access,
+        _: Access,
+        _: Start,
+        _: Start,
+        _: Start,
+        _: Span,
+    ) -> Span {
+        Span
+    }
+}
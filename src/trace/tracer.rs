@@ -0,0 +1,85 @@
+use crate::raw::{nanos, Access, Start};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tracing::Span;
+
+// in nanoseconds, an acquisition that waits for longer than `SLOW_ACQUIRE`, or a guard
+// that is held for longer than `LONG_HOLD`, emits a warning
+static SLOW_ACQUIRE: AtomicU64 = AtomicU64::new(10_000_000);
+static LONG_HOLD: AtomicU64 = AtomicU64::new(100_000_000);
+
+// the waiting time above which an acquisition emits a warning, 10ms by default
+pub fn set_slow_acquire_threshold(threshold: Duration) {
+    SLOW_ACQUIRE.store(nanos(threshold), Ordering::Relaxed);
+}
+
+// the holding time above which a guard emits a warning when it is released, 100ms by default
+pub fn set_long_hold_threshold(threshold: Duration) {
+    LONG_HOLD.store(nanos(threshold), Ordering::Relaxed);
+}
+
+fn exceeds(duration: Duration, threshold: &AtomicU64) -> bool {
+    nanos(duration) > threshold.load(Ordering::Relaxed)
+}
+
+// the `tracing` instrumentation of a single lock
+pub(crate) struct Tracer {
+    name: &'static str,
+}
+
+impl Tracer {
+    pub(crate) const fn new() -> Self {
+        Tracer { name: "unnamed" }
+    }
+    pub(crate) const fn set_name(&mut self, name: &'static str) {
+        self.name = name;
+    }
+    // returns the span of the guard, `now` is when the lock was acquired
+    pub(crate) fn locked(&self, access: Access, start: Start, now: Start) -> Span {
+        let waited = now.at - start.at;
+        if exceeds(waited, &SLOW_ACQUIRE) {
+            tracing::warn!(lock = self.name, ?access, ?waited, "slow lock acquisition");
+        }
+        self.span(access)
+    }
+    fn span(&self, access: Access) -> Span {
+        if access.is_write() {
+            tracing::debug_span!("write_guard", lock = self.name)
+        } else {
+            tracing::debug_span!("read_guard", lock = self.name)
+        }
+    }
+    // a guard held since `since` has been released, which closes its span
+    pub(crate) fn unlocked(&self, access: Access, since: Start, span: Span) {
+        let duration = since.at.elapsed();
+        if exceeds(duration, &LONG_HOLD) {
+            tracing::warn!(
+                parent: &span,
+                lock = self.name,
+                ?access,
+                held = ?duration,
+                "lock held for a long time"
+            );
+        }
+    }
+    // the held lock has been upgraded or downgraded at `now`, which ends one guard and
+    // starts another
+    pub(crate) fn relocked(
+        &self,
+        from: Access,
+        to: Access,
+        start: Start,
+        since: Start,
+        now: Start,
+        span: Span,
+    ) -> Span {
+        self.unlocked(from, since, span);
+        match to {
+            // an upgrade, which waits like an acquisition
+            Access::Write => self.locked(Access::Upgrade, start, now),
+            _ => self.span(to),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+use rwlock::{ArcLockGuard, ArcReadOnlyGuard, RWLock};
+use std::{sync::Arc, thread};
+
+// the guards own their lock, so they can be moved into other threads as they are
+#[test]
+fn moved_into_threads() {
+    let lock = Arc::new(RWLock::new(0));
+    let writer: ArcLockGuard<i32> = lock.write_arc();
+    let writer = thread::spawn(move || {
+        let mut writer = writer;
+        *writer += 1;
+        writer
+    })
+    .join()
+    .unwrap();
+    assert!(lock.try_read_arc().is_none());
+    drop(writer);
+
+    let readers: Vec<ArcReadOnlyGuard<i32>> = (0..4).map(|_| lock.read_arc()).collect();
+    assert!(lock.try_write_arc().is_none());
+    let sum: i32 = thread::spawn(move || readers.iter().map(|reader| **reader).sum())
+        .join()
+        .unwrap();
+    assert_eq!(sum, 4);
+    assert!(lock.try_write_arc().is_some());
+}
+
+// a guard keeps the lock alive after every other handle on it is gone
+#[test]
+fn outlives_the_handle() {
+    let lock = Arc::new(RWLock::new(String::from("kept")));
+    let reader = lock.read_arc();
+    drop(lock);
+    assert_eq!(*reader, "kept");
+    assert_eq!(Arc::strong_count(reader.lock()), 1);
+}
+
+#[test]
+fn downgrade() {
+    let lock = Arc::new(RWLock::new(0));
+    let mut writer = lock.write_arc();
+    *writer += 1;
+    let reader = writer.downgrade();
+    assert_eq!(*reader, 1);
+    // a reader now, which other readers may join but no writer
+    assert!(lock.try_read_arc().is_some());
+    assert!(lock.try_write_arc().is_none());
+    assert!(Arc::ptr_eq(reader.lock(), &lock));
+    // moved to another thread and released there
+    thread::spawn(move || drop(reader)).join().unwrap();
+    *lock.try_write_arc().unwrap() += 1;
+    assert_eq!(*lock.read(), 2);
+}
@@ -0,0 +1,142 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+use rwlock::{Policy, RWLock};
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+// a minimal executor, the lock futures depend on nothing but the wakers
+struct ThreadWaker(Thread);
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn poll_once<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    future.poll(&mut Context::from_waker(&waker))
+}
+
+const POLICIES: [Policy; 3] = [
+    Policy::ReaderPreferred,
+    Policy::WriterPreferred,
+    Policy::Fair,
+];
+
+// async and blocking users of the same lock
+#[test]
+fn mixed_with_blocking() {
+    for policy in POLICIES {
+        let lock = Arc::new(RWLock::with_policy(0u64, policy));
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        match i % 4 {
+                            0 => *block_on(lock.write_async()) += 1,
+                            1 => *lock.write() += 1,
+                            2 => assert!(*block_on(lock.read_async()) <= 2000),
+                            _ => assert!(*lock.read() <= 2000),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*lock.read(), 2000, "{:?}", policy);
+    }
+}
+
+// a future that is still waiting when dropped leaves the lock usable under every policy
+#[test]
+fn dropped_while_waiting() {
+    for policy in POLICIES {
+        let lock = RWLock::with_policy(0, policy);
+        let reader = lock.read();
+        let mut write = Box::pin(lock.write_async());
+        assert!(poll_once(write.as_mut()).is_pending());
+        drop(write);
+        drop(reader);
+        assert!(lock.try_write().is_some(), "{:?}", policy);
+        *block_on(lock.write_async()) += 1;
+        assert_eq!(*block_on(lock.read_async()), 1);
+    }
+}
+
+// the readers back off for a waiting writer only until its future is dropped
+#[test]
+fn dropped_writer_is_no_longer_waiting() {
+    let lock = RWLock::with_policy(0, Policy::WriterPreferred);
+    let reader = lock.read();
+    let mut write = Box::pin(lock.write_async());
+    assert!(poll_once(write.as_mut()).is_pending());
+    assert!(lock.try_read().is_none());
+    drop(write);
+    assert!(lock.try_read().is_some());
+    drop(reader);
+}
+
+// the queue moves past the ticket of a dropped future, whether its turn has come or not
+#[test]
+fn dropped_ticket_is_skipped() {
+    let lock = RWLock::with_policy(0, Policy::Fair);
+    let writer = lock.write();
+    // its turn comes right away, but the writer is in the way
+    let mut read = Box::pin(lock.read_async());
+    assert!(poll_once(read.as_mut()).is_pending());
+    // queued behind the reader
+    let mut write = Box::pin(lock.write_async());
+    assert!(poll_once(write.as_mut()).is_pending());
+    drop(write);
+    drop(writer);
+    drop(block_on(read));
+    // nobody is queued anymore, or a single attempt would not succeed
+    assert!(lock.try_write().is_some());
+
+    let writer = lock.write();
+    let mut read = Box::pin(lock.read_async());
+    assert!(poll_once(read.as_mut()).is_pending());
+    drop(read);
+    drop(writer);
+    assert!(lock.try_read().is_some());
+    *block_on(lock.write_async()) += 1;
+}
+
+// a guard held across an await belongs to its task, so the next task run by the same
+// thread may take the same lock, or other locks, without it counting against the thread
+#[test]
+#[cfg(feature = "deadlock_detection")]
+fn guard_is_held_by_the_task() {
+    let lock = RWLock::with_policy(0, Policy::WriterPreferred);
+    let task = block_on(lock.read_async());
+    drop(lock.read());
+    drop(task);
+
+    let other = RWLock::new(0);
+    let task = block_on(lock.write_async());
+    drop(other.write());
+    drop(task);
+    // the opposite order of the one above, had the thread held the guard of the task
+    let _other = other.write();
+    drop(lock.write());
+}
@@ -0,0 +1,9 @@
+// the ui cases use the arc guards, which need the `std` feature
+#![cfg(feature = "std")]
+
+#[test]
+fn auto_traits() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass/*.rs");
+    t.compile_fail("tests/ui/fail/*.rs");
+}
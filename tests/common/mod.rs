@@ -0,0 +1,45 @@
+// the stress test shared by the lock types, along with the pair it hammers
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+const THREADS: usize = 8;
+
+// a lock around a pair whose halves are only ever changed together, so a reader seeing
+// them differ has seen a half-done write, `round` lets the lock vary the kind of
+// acquisition from one round to the next
+pub trait PairLock: Sync {
+    // `None` if the acquisition is a single attempt that failed
+    fn read_pair(&self, round: usize) -> Option<(usize, usize)>;
+    // increments both halves, returns `false` if the acquisition is a single attempt
+    // that failed
+    fn write_pair(&self, round: usize) -> bool;
+}
+
+// every thread writes in one round out of four and reads in the others, returns the
+// number of writes that took place
+pub fn hammer(lock: &impl PairLock, rounds: usize) -> usize {
+    let writes = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for i in 0..THREADS {
+            let writes = &writes;
+            scope.spawn(move || {
+                let mut last = 0;
+                for round in 0..rounds {
+                    if (i + round) % 4 == 0 {
+                        if lock.write_pair(round) {
+                            writes.fetch_add(1, Ordering::Relaxed);
+                        }
+                    } else if let Some((a, b)) = lock.read_pair(round) {
+                        assert_eq!(a, b);
+                        // the halves only ever grow, so neither may a reader's view of them
+                        assert!(a >= last);
+                        last = a;
+                    }
+                }
+            });
+        }
+    });
+    writes.into_inner()
+}
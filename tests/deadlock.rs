@@ -0,0 +1,190 @@
+// run with `cargo test --features deadlock_detection`
+#![cfg(feature = "deadlock_detection")]
+
+use rwlock::{LockClass, LockGuard, Policy, RWLock};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+};
+
+fn deadlock_reason(f: impl FnOnce()) -> String {
+    let payload = catch_unwind(AssertUnwindSafe(f)).expect_err("no deadlock reported");
+    let message = payload
+        .downcast_ref::<String>()
+        .expect("not a deadlock report");
+    assert!(message.starts_with("deadlock detected"), "{}", message);
+    message.clone()
+}
+
+#[test]
+fn write_while_reading() {
+    let lock = RWLock::new(0);
+    let reason = deadlock_reason(|| {
+        let _guard = lock.read();
+        let _guard = lock.write();
+    });
+    assert!(reason.contains("the write lock is requested"));
+    // the guard released by the panic is no longer considered held
+    drop(lock.write());
+}
+
+#[test]
+fn write_while_writing() {
+    let lock = RWLock::new(0);
+    deadlock_reason(|| {
+        let _guard = lock.write();
+        let _guard = lock.write();
+    });
+}
+
+#[test]
+fn read_while_writing() {
+    let lock = RWLock::new(0);
+    deadlock_reason(|| {
+        let _guard = lock.write();
+        let _guard = lock.read_recursive();
+    });
+}
+
+// a mapped guard keeps the lock it was mapped from held
+#[test]
+fn read_while_writing_through_mapped_guard() {
+    let lock = RWLock::new((0, 0));
+    deadlock_reason(|| {
+        let _guard = LockGuard::map(lock.write(), |(a, _)| a);
+        let _guard = lock.read();
+    });
+}
+
+#[test]
+fn upgrade_while_reading() {
+    let lock = RWLock::new(0);
+    deadlock_reason(|| {
+        let upgradable = lock.upgradable_read();
+        let _guard = lock.read();
+        let _guard = upgradable.upgrade();
+    });
+    // the upgradable guard released by the panic is no longer held either
+    drop(lock.try_upgradable_read().unwrap());
+    drop(lock.try_write().unwrap());
+}
+
+// reported under every policy, a writer that has got `UPGRADABLE` waits for our read lock
+#[test]
+fn upgradable_while_reading() {
+    let lock = RWLock::new(0);
+    let reason = deadlock_reason(|| {
+        let _guard = lock.read();
+        let _guard = lock.upgradable_read();
+    });
+    assert!(reason.contains("the upgradable read lock is requested"));
+    drop(lock.upgradable_read());
+}
+
+#[test]
+fn nested_reads_depend_on_policy() {
+    let lock = RWLock::new(0);
+    let _outer = lock.read();
+    let _inner = lock.read();
+
+    let lock = RWLock::with_policy(0, Policy::WriterPreferred);
+    deadlock_reason(|| {
+        let _outer = lock.read();
+        let _inner = lock.read();
+    });
+    let _outer = lock.read();
+    let _inner = lock.read_recursive();
+}
+
+#[test]
+fn downgraded_guard_is_a_reader() {
+    let lock = RWLock::new(0);
+    let guard = lock.write().downgrade();
+    let _guard = lock.read();
+    drop(guard);
+    deadlock_reason(|| {
+        let _guard = lock.write();
+    });
+}
+
+// the lock is no longer held by the thread that took it once the guard is released
+// on another one
+#[test]
+fn guard_released_on_another_thread() {
+    let lock = Arc::new(RWLock::new(0));
+    let guard = lock.write_arc();
+    thread::spawn(move || drop(guard)).join().unwrap();
+    drop(lock.write());
+
+    // while it lives, the guard still counts for the thread that took it
+    let guard = lock.read_arc();
+    let guard = thread::spawn(move || guard).join().unwrap();
+    deadlock_reason(|| drop(lock.write()));
+    drop(guard);
+}
+
+#[test]
+fn lock_order_cycle() {
+    let a = RWLock::new(0);
+    let b = RWLock::new(0);
+    {
+        let _a = a.write();
+        let _b = b.read();
+    }
+    let reason = deadlock_reason(|| {
+        let _b = b.write();
+        let _a = a.read();
+    });
+    assert!(reason.contains("opposite order"));
+    assert!(reason.contains("the opposite lock order was taken at"));
+    // the same order as before is fine
+    let _a = a.write();
+    let _b = b.write();
+}
+
+#[test]
+fn timed_and_try_acquisitions_cannot_deadlock() {
+    let lock = RWLock::new(0);
+    let _guard = lock.read();
+    assert!(lock.try_write().is_none());
+    assert!(lock
+        .try_write_for(std::time::Duration::from_millis(1))
+        .is_none());
+}
+
+// the order taken with some locks is checked against the other locks of their classes
+#[test]
+fn lock_class_order_cycle() {
+    const ACCOUNT: LockClass = LockClass::new("account");
+    const LEDGER: LockClass = LockClass::new("ledger");
+    let account = RWLock::new(0).with_class(ACCOUNT);
+    let ledger = RWLock::new(0).with_class(LEDGER);
+    drop((account.read(), ledger.write()));
+
+    let other_account = RWLock::new(0).with_class(ACCOUNT);
+    let other_ledger = RWLock::new(0).with_class(LEDGER);
+    let reason = deadlock_reason(|| {
+        let _ledger = other_ledger.read();
+        let _account = other_account.write();
+    });
+    assert!(reason.contains("opposite order"));
+    // the locks of a single class may be nested
+    drop((account.write(), other_account.write()));
+    drop((other_account.write(), account.write()));
+}
+
+#[test]
+fn lock_class_levels() {
+    let outer = RWLock::new(0).with_class(LockClass::new("outer").with_level(1));
+    let inner = RWLock::new(0).with_class(LockClass::new("inner").with_level(2));
+    drop((outer.read(), inner.read()));
+    // reported before the opposite order has ever been taken
+    let fresh = RWLock::new(0).with_class(LockClass::new("fresh").with_level(0));
+    let reason = deadlock_reason(|| {
+        let _inner = inner.read();
+        let _fresh = fresh.read();
+    });
+    assert!(reason.contains("level 0"));
+    assert!(reason.contains("level 2"));
+}
@@ -0,0 +1,26 @@
+// run with `cargo test --features deadlock_detection`, the handler is process-wide
+// so it gets a test binary of its own
+#![cfg(feature = "deadlock_detection")]
+
+use rwlock::{set_deadlock_handler, RWLock};
+use std::sync::Mutex;
+
+static REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[test]
+fn handler_replaces_panic() {
+    set_deadlock_handler(|deadlock| {
+        assert!(deadlock.reversed_backtrace().is_some());
+        REPORTS.lock().unwrap().push(deadlock.reason().to_owned());
+    });
+    let a = RWLock::new(0);
+    let b = RWLock::new(0);
+    drop((a.read(), b.write()));
+    // the acquisition goes ahead after the handler, which cannot deadlock on a single thread
+    drop((b.read(), a.write()));
+    // an inversion is reported the first time only
+    drop((b.read(), a.write()));
+    let reports = REPORTS.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].contains("opposite order"));
+}
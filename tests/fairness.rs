@@ -0,0 +1,109 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+use rwlock::{Policy, RWLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// readers continuously overlap each other while a few writers try to get in, returns the
+// longest wait of each class
+fn run(policy: Policy) -> (Duration, Duration) {
+    let lock = Arc::new(RWLock::with_policy(0u64, policy));
+    let stop = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..6)
+        .map(|_| {
+            let lock = lock.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut max_wait = Duration::ZERO;
+                while !stop.load(Ordering::Relaxed) {
+                    let start = Instant::now();
+                    let r = lock.read();
+                    max_wait = max_wait.max(start.elapsed());
+                    thread::sleep(Duration::from_micros(200));
+                    drop(r);
+                }
+                max_wait
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let mut max_wait = Duration::ZERO;
+                for _ in 0..20 {
+                    let start = Instant::now();
+                    // give up after a while, as a starving writer would never return otherwise
+                    match lock.try_write_for(Duration::from_millis(200)) {
+                        Some(mut w) => {
+                            max_wait = max_wait.max(start.elapsed());
+                            *w += 1;
+                            thread::sleep(Duration::from_micros(200));
+                        }
+                        None => max_wait = max_wait.max(start.elapsed()),
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                max_wait
+            })
+        })
+        .collect();
+    let writer_wait = writers
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .max()
+        .unwrap();
+    stop.store(true, Ordering::Relaxed);
+    let reader_wait = readers
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .max()
+        .unwrap();
+    (reader_wait, writer_wait)
+}
+
+#[test]
+fn bounded_wait() {
+    let (reader_wait, writer_wait) = run(Policy::Fair);
+    // everyone only waits for those queued before it, i.e. at most for each of the other
+    // threads holding the lock once
+    assert!(writer_wait < Duration::from_millis(100), "writer starved");
+    assert!(reader_wait < Duration::from_millis(100), "reader starved");
+}
+
+// an upgrade only waits for the readers already holding the lock, the ones arriving
+// after it queue up behind it like behind a writer
+#[test]
+fn bounded_upgrade() {
+    let lock = Arc::new(RWLock::with_policy(0u64, Policy::Fair));
+    let stop = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let r = lock.read();
+                    thread::sleep(Duration::from_millis(1));
+                    drop(r);
+                }
+            })
+        })
+        .collect();
+    // let the readers overlap
+    thread::sleep(Duration::from_millis(20));
+    let mut max_wait = Duration::ZERO;
+    for _ in 0..5 {
+        let guard = lock.upgradable_read();
+        let start = Instant::now();
+        *guard.upgrade() += 1;
+        max_wait = max_wait.max(start.elapsed());
+    }
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert!(max_wait < Duration::from_millis(100), "upgrade starved");
+}
@@ -0,0 +1,286 @@
+// model-checks the lock state machine with loom, run it with
+// RUSTFLAGS="--cfg loom" cargo test --test loom --release
+#![cfg(loom)]
+
+use loom::{
+    cell::UnsafeCell,
+    model::Builder,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+use rwlock::{Park, Policy, RWLock, ShardedRWLock, Spin, WaitStrategy};
+use std::time::Instant;
+
+// the data lives in a loom cell, which reports any access that is not ordered
+// by the lock as a data race
+struct Data(UnsafeCell<usize>);
+
+impl Data {
+    fn read(&self) -> usize {
+        self.0.with(|v| unsafe { *v })
+    }
+    fn increment(&self) {
+        self.0.with_mut(|v| unsafe { *v += 1 });
+    }
+}
+
+// blocks a waiter until the next release, unlike spinning it does not make loom explore
+// every number of rounds a waiter may spin, and a wrong `should_block` shows up as a deadlock
+#[derive(Default)]
+struct Block {
+    released: Mutex<()>,
+    waiters: Condvar,
+}
+
+impl WaitStrategy for Block {
+    fn wait<F: Fn() -> bool>(&self, _: &mut u32, should_block: F, _: Option<Instant>) {
+        let released = self.released.lock().unwrap();
+        if should_block() {
+            drop(self.waiters.wait(released).unwrap());
+        }
+    }
+    fn notify(&self) {
+        let _released = self.released.lock().unwrap();
+        self.waiters.notify_all();
+    }
+}
+
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = Builder::new();
+    // the spinning waiters make the unbounded exploration far too large
+    builder.preemption_bound = Some(3);
+    builder.max_branches = 100_000;
+    builder.check(f);
+}
+
+fn lock<W: WaitStrategy>(policy: Policy, strategy: W) -> Arc<RWLock<Data, W>> {
+    Arc::new(RWLock::with_policy_and_strategy(
+        Data(UnsafeCell::new(0)),
+        policy,
+        strategy,
+    ))
+}
+
+fn reader_and_writer(policy: Policy) {
+    model(move || {
+        let lock = lock(policy, Spin);
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let value = lock.read().read();
+                assert!(value <= 1);
+            })
+        };
+        lock.write().increment();
+        reader.join().unwrap();
+        assert_eq!(lock.read().read(), 1);
+    });
+}
+
+#[test]
+fn reader_and_writer_reader_preferred() {
+    reader_and_writer(Policy::ReaderPreferred);
+}
+
+#[test]
+fn reader_and_writer_writer_preferred() {
+    reader_and_writer(Policy::WriterPreferred);
+}
+
+#[test]
+fn reader_and_writer_fair() {
+    reader_and_writer(Policy::Fair);
+}
+
+#[test]
+fn writers() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Spin);
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let lock = lock.clone();
+                thread::spawn(move || lock.write().increment())
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(lock.read().read(), 2);
+    });
+}
+
+#[test]
+fn try_lock() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Spin);
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                if let Some(guard) = lock.try_write() {
+                    guard.increment();
+                }
+            })
+        };
+        if let Some(guard) = lock.try_read() {
+            assert!(guard.read() <= 1);
+        }
+        other.join().unwrap();
+        // a failed attempt must not leave the lock held
+        assert!(lock.try_write().is_some());
+    });
+}
+
+#[test]
+fn upgrade() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Spin);
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.write().increment())
+        };
+        let guard = lock.upgradable_read();
+        let before = guard.read();
+        let guard = guard.upgrade();
+        // no writer gets in between the read and the upgrade
+        assert_eq!(guard.read(), before);
+        guard.increment();
+        drop(guard);
+        writer.join().unwrap();
+        assert_eq!(lock.read().read(), 2);
+    });
+}
+
+// the upgrade waits for the other reader, and must be woken up once it leaves
+#[test]
+fn upgrade_waits_for_reader() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Block::default());
+        let guard = lock.upgradable_read();
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.read().read())
+        };
+        let guard = guard.upgrade();
+        guard.increment();
+        drop(guard);
+        assert!(reader.join().unwrap() <= 1);
+        assert_eq!(lock.read().read(), 1);
+    });
+}
+
+#[test]
+fn downgrade() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Spin);
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.write().increment())
+        };
+        let guard = lock.write();
+        guard.increment();
+        let before = guard.read();
+        let guard = guard.downgrade();
+        assert_eq!(guard.read(), before);
+        drop(guard);
+        writer.join().unwrap();
+        assert_eq!(lock.read().read(), 2);
+    });
+}
+
+// a waiter that parks must be woken up by the release it waits for
+#[test]
+fn park() {
+    model(|| {
+        let lock = lock(Policy::ReaderPreferred, Park::new());
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.write().increment())
+        };
+        lock.write().increment();
+        writer.join().unwrap();
+        assert_eq!(lock.read().read(), 2);
+    });
+}
+
+// a reader that joins while the last reader is leaving must keep the writer out
+// until it has left as well
+fn reader_joins_while_last_reader_leaves(policy: Policy) {
+    model(move || {
+        let lock = lock(policy, Block::default());
+        let guard = lock.read();
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let value = lock.read().read();
+                assert!(value <= 1);
+            })
+        };
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.write().increment())
+        };
+        drop(guard);
+        reader.join().unwrap();
+        writer.join().unwrap();
+        assert_eq!(lock.read().read(), 1);
+    });
+}
+
+#[test]
+fn reader_joins_while_last_reader_leaves_reader_preferred() {
+    reader_joins_while_last_reader_leaves(Policy::ReaderPreferred);
+}
+
+#[test]
+fn reader_joins_while_last_reader_leaves_writer_preferred() {
+    reader_joins_while_last_reader_leaves(Policy::WriterPreferred);
+}
+
+#[test]
+fn reader_joins_while_last_reader_leaves_fair() {
+    reader_joins_while_last_reader_leaves(Policy::Fair);
+}
+
+fn sharded<W: WaitStrategy>(strategy: W) -> Arc<ShardedRWLock<Data, W>> {
+    Arc::new(ShardedRWLock::with_strategy(
+        Data(UnsafeCell::new(0)),
+        strategy,
+    ))
+}
+
+// a reader coming in while the writer announces itself must either be seen by the writer
+// or see the writer and back off
+#[test]
+fn sharded_reader_and_writer() {
+    model(|| {
+        let lock = sharded(Block::default());
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let value = lock.read().read();
+                assert!(value <= 1);
+            })
+        };
+        lock.write().increment();
+        reader.join().unwrap();
+        assert_eq!(lock.read().read(), 1);
+    });
+}
+
+#[test]
+fn sharded_writers() {
+    model(|| {
+        let lock = sharded(Block::default());
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.write().increment())
+        };
+        let guard = lock.write();
+        guard.increment();
+        let guard = guard.downgrade();
+        assert!(guard.read() >= 1);
+        drop(guard);
+        writer.join().unwrap();
+        assert_eq!(lock.read().read(), 2);
+    });
+}
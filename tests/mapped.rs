@@ -0,0 +1,76 @@
+#![cfg(not(loom))]
+
+use rwlock::{LockGuard, MappedLockGuard, MappedReadOnlyGuard, RWLock, ReadOnlyGuard};
+
+#[derive(Debug)]
+struct Config {
+    name: String,
+    ports: Vec<u16>,
+}
+
+fn config() -> RWLock<Config> {
+    RWLock::new(Config {
+        name: "server".to_owned(),
+        ports: vec![80, 443],
+    })
+}
+
+#[test]
+fn read_map() {
+    let lock = config();
+    let ports = ReadOnlyGuard::map(lock.read(), |config| &config.ports);
+    assert_eq!(*ports, [80, 443]);
+    // the whole lock stays read-locked until the mapped guard is dropped
+    assert!(lock.try_write().is_none());
+    let first = MappedReadOnlyGuard::map(ports, |ports| &ports[0]);
+    assert_eq!(*first, 80);
+    assert!(lock.try_write().is_none());
+    drop(first);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn read_try_map() {
+    let lock = config();
+    // nothing to narrow down to, the original guard comes back still holding the lock
+    let guard = ReadOnlyGuard::try_map(lock.read(), |config| config.ports.get(5)).unwrap_err();
+    assert!(lock.try_write().is_none());
+    let ports = ReadOnlyGuard::try_map(guard, |config| Some(&config.ports)).unwrap();
+    let ports = MappedReadOnlyGuard::try_map(ports, |ports| ports.get(5)).unwrap_err();
+    assert!(lock.try_write().is_none());
+    let last = MappedReadOnlyGuard::try_map(ports, |ports| ports.last()).unwrap();
+    assert_eq!(*last, 443);
+    drop(last);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn write_map() {
+    let lock = config();
+    let mut ports = LockGuard::map(lock.write(), |config| &mut config.ports);
+    ports.push(8080);
+    // the whole lock stays write-locked until the mapped guard is dropped
+    assert!(lock.try_read().is_none());
+    let mut last = MappedLockGuard::map(ports, |ports| ports.last_mut().unwrap());
+    *last += 1;
+    assert!(lock.try_read().is_none());
+    drop(last);
+    assert_eq!(lock.read().ports, [80, 443, 8081]);
+}
+
+#[test]
+fn write_try_map() {
+    let lock = config();
+    let guard = LockGuard::try_map(lock.write(), |config| config.ports.get_mut(5)).unwrap_err();
+    assert!(lock.try_read().is_none());
+    let mut name = LockGuard::try_map(guard, |config| Some(&mut config.name)).unwrap();
+    name.clear();
+    let mut name =
+        MappedLockGuard::try_map(name, |name| (!name.is_empty()).then_some(name)).unwrap_err();
+    assert!(lock.try_read().is_none());
+    name.push_str("proxy");
+    let mut name = MappedLockGuard::try_map(name, |name| Some(name)).unwrap();
+    name.push('!');
+    drop(name);
+    assert_eq!(lock.read().name, "proxy!");
+}
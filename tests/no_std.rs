@@ -0,0 +1,63 @@
+// checks that the lock is usable from a `no_std` crate, run it with
+// `cargo test --no-default-features --test no_std` to build the lock itself without std
+#![no_std]
+#![cfg(not(loom))]
+
+use core::hint;
+use rwlock::{
+    Instant, LockGuard, Park, Policy, RWLock, ReadOnlyGuard, SeqLock, Spin, WaitStrategy,
+};
+
+static COUNTER: RWLock<u32> = RWLock::new(0);
+static FAIR: RWLock<u32, Spin> = RWLock::with_policy_and_strategy(0, Policy::Fair, Spin::new());
+static CLOCK: SeqLock<(u32, u32)> = SeqLock::new((0, 0));
+
+fn increment<W: WaitStrategy>(lock: &RWLock<u32, W>) -> u32 {
+    let mut guard: LockGuard<'_, u32, W> = lock.write();
+    *guard += 1;
+    let guard: ReadOnlyGuard<'_, u32, W> = guard.downgrade();
+    *guard
+}
+
+#[test]
+fn guards() {
+    assert_eq!(increment(&COUNTER), 1);
+    assert_eq!(increment(&FAIR), 1);
+    let reader = COUNTER.read();
+    assert!(COUNTER.try_write().is_none());
+    assert_eq!(*COUNTER.try_read().unwrap(), *reader);
+}
+
+#[test]
+fn upgrade() {
+    let lock = RWLock::with_strategy(0, Park::new());
+    let guard = lock.upgradable_read();
+    let reader = lock.read();
+    let guard = guard.try_upgrade().unwrap_err();
+    drop(reader);
+    *guard.upgrade() += 1;
+    assert_eq!(lock.into_inner(), 1);
+}
+
+#[test]
+fn seqlock() {
+    *CLOCK.write() = (1, 2);
+    assert_eq!(CLOCK.read(), (1, 2));
+}
+
+// a strategy of our own, written against `rwlock::Instant` so that it builds whether or not
+// some other crate turns on `rwlock/std`
+struct MySpin;
+impl WaitStrategy for MySpin {
+    fn wait<F: Fn() -> bool>(&self, _: &mut u32, _: F, _: Option<Instant>) {
+        hint::spin_loop();
+    }
+    fn notify(&self) {}
+}
+
+#[test]
+fn custom_strategy() {
+    let lock = RWLock::with_strategy(0, MySpin);
+    *lock.write() += 1;
+    assert_eq!(*lock.read(), 1);
+}
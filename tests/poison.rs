@@ -0,0 +1,83 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+use rwlock::PoisonRWLock;
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::TryLockError,
+};
+
+#[test]
+fn panicking_writer_poisons() {
+    let lock = PoisonRWLock::new(vec![1, 2]);
+    catch_unwind(AssertUnwindSafe(|| {
+        let mut guard = lock.write().unwrap();
+        guard.push(3);
+        panic!("half-done write");
+    }))
+    .unwrap_err();
+    assert!(lock.is_poisoned());
+    // the guard comes along with the error, showing what the writer left behind
+    assert_eq!(*lock.read().unwrap_err().into_inner(), [1, 2, 3]);
+    assert!(lock.write().is_err());
+    assert!(matches!(lock.try_read(), Err(TryLockError::Poisoned(_))));
+    assert!(matches!(lock.try_write(), Err(TryLockError::Poisoned(_))));
+    assert!(format!("{:?}", lock).contains("poisoned: true"));
+
+    lock.clear_poison();
+    assert!(!lock.is_poisoned());
+    lock.write().unwrap().pop();
+    assert_eq!(lock.into_inner().unwrap(), [1, 2]);
+}
+
+#[test]
+fn poisoned_owned_access() {
+    let mut lock = PoisonRWLock::new(0);
+    catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.write().unwrap();
+        panic!("half-done write");
+    }))
+    .unwrap_err();
+    *lock.get_mut().unwrap_err().into_inner() += 1;
+    assert_eq!(lock.into_inner().unwrap_err().into_inner(), 1);
+}
+
+// a reader cannot have half-mutated the data
+#[test]
+fn panicking_reader_does_not_poison() {
+    let lock = PoisonRWLock::new(0);
+    catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.read().unwrap();
+        panic!("while reading");
+    }))
+    .unwrap_err();
+    assert!(!lock.is_poisoned());
+    assert!(lock.try_write().is_ok());
+}
+
+// a guard taken while the thread is already unwinding, as in a `Drop`, finishes its work
+// before the panic goes on, so it does not poison the lock
+#[test]
+fn guard_taken_while_panicking() {
+    struct IncrementOnDrop<'a>(&'a PoisonRWLock<u8>);
+    impl Drop for IncrementOnDrop<'_> {
+        fn drop(&mut self) {
+            *self.0.write().unwrap() += 1;
+        }
+    }
+    let lock = PoisonRWLock::new(0);
+    catch_unwind(AssertUnwindSafe(|| {
+        let _increment = IncrementOnDrop(&lock);
+        panic!("unwinding through the drop");
+    }))
+    .unwrap_err();
+    assert!(!lock.is_poisoned());
+    assert_eq!(*lock.read().unwrap(), 1);
+}
+
+#[test]
+fn would_block_is_not_poisoned() {
+    let lock = PoisonRWLock::new(0);
+    let _guard = lock.write().unwrap();
+    assert!(matches!(lock.try_read(), Err(TryLockError::WouldBlock)));
+    assert!(matches!(lock.try_write(), Err(TryLockError::WouldBlock)));
+}
@@ -0,0 +1,57 @@
+use rwlock::{Policy, RWLock};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+// a thread holding a read guard takes nested ones while a writer waits for the lock
+fn nested_reads_with_waiting_writer(policy: Policy) {
+    let lock = Arc::new(RWLock::with_policy(0, policy));
+    let outer = lock.read();
+    let writer = {
+        let lock = lock.clone();
+        thread::spawn(move || *lock.write() += 1)
+    };
+    // wait until the writer has queued up, from then on `read` would no longer get in
+    // under the writer-preferring policies
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while policy != Policy::ReaderPreferred && lock.try_read().is_some() {
+        assert!(Instant::now() < deadline, "writer did not queue up");
+        thread::yield_now();
+    }
+    {
+        let inner = lock.read_recursive();
+        let innermost = lock.try_read_recursive().expect("read guard held");
+        assert_eq!(*inner, 0);
+        assert_eq!(*innermost, 0);
+    }
+    assert_eq!(*outer, 0);
+    drop(outer);
+    writer.join().unwrap();
+    assert_eq!(*lock.read_recursive(), 1);
+}
+
+#[test]
+fn nested_reads_reader_preferred() {
+    nested_reads_with_waiting_writer(Policy::ReaderPreferred);
+}
+
+#[test]
+fn nested_reads_writer_preferred() {
+    nested_reads_with_waiting_writer(Policy::WriterPreferred);
+}
+
+#[test]
+fn nested_reads_fair() {
+    nested_reads_with_waiting_writer(Policy::Fair);
+}
+
+#[test]
+fn recursive_read_waits_for_writer() {
+    let lock = RWLock::new(0);
+    let guard = lock.write();
+    assert!(lock.try_read_recursive().is_none());
+    drop(guard);
+    assert!(lock.try_read_recursive().is_some());
+}
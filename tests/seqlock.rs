@@ -0,0 +1,74 @@
+#![cfg(not(loom))]
+
+mod common;
+
+use common::PairLock;
+use rwlock::{SeqLock, Spin, WaitStrategy};
+use std::{sync::Arc, thread};
+
+const ROUNDS: usize = 5000;
+
+// the reads are optimistic copies, so a torn one is what the pair check would catch
+impl<W: WaitStrategy + Sync> PairLock for SeqLock<(usize, usize), W> {
+    fn read_pair(&self, round: usize) -> Option<(usize, usize)> {
+        match round % 2 {
+            0 => self.try_read(),
+            _ => Some(self.read()),
+        }
+    }
+    fn write_pair(&self, round: usize) -> bool {
+        let Some(mut guard) = (match round % 2 {
+            0 => self.try_write(),
+            _ => Some(self.write()),
+        }) else {
+            return false;
+        };
+        guard.0 += 1;
+        guard.1 += 1;
+        true
+    }
+}
+
+fn hammer<W: WaitStrategy + Sync>(lock: SeqLock<(usize, usize), W>) {
+    let writes = common::hammer(&lock, ROUNDS);
+    assert_eq!(lock.read(), (writes, writes));
+}
+
+#[test]
+fn park() {
+    hammer(SeqLock::new((0, 0)));
+}
+
+#[test]
+fn spin() {
+    hammer(SeqLock::with_strategy((0, 0), Spin::new()));
+}
+
+#[test]
+fn writer_excludes() {
+    let lock = SeqLock::new(1);
+    let mut guard = lock.write();
+    *guard += 1;
+    assert_eq!(lock.try_read(), None);
+    assert!(lock.try_write().is_none());
+    assert!(format!("{:?}", lock).contains("data: <locked>"));
+    drop(guard);
+    assert_eq!(lock.try_read(), Some(2));
+    assert_eq!(lock.read(), 2);
+    *lock.try_write().unwrap() += 1;
+    assert_eq!(lock.into_inner(), 3);
+}
+
+// a reader that finds a writer holding the lock waits for it instead of failing
+#[test]
+fn read_waits_for_writer() {
+    let lock = Arc::new(SeqLock::<u32>::from(0));
+    let guard = lock.write();
+    let reader = {
+        let lock = lock.clone();
+        thread::spawn(move || lock.read())
+    };
+    thread::sleep(std::time::Duration::from_millis(10));
+    drop(guard);
+    assert_eq!(reader.join().unwrap(), 0);
+}
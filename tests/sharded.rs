@@ -0,0 +1,94 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+mod common;
+
+use common::PairLock;
+use rwlock::{ShardedRWLock, Spin, SpinThenYield, WaitStrategy};
+use std::{sync::Arc, thread};
+
+const ROUNDS: usize = 2000;
+
+// every kind of acquisition in turn, including a downgrade and the attempts that fail
+// whenever a writer has raised its flag
+impl<W: WaitStrategy + Sync> PairLock for ShardedRWLock<(usize, usize), W> {
+    fn read_pair(&self, round: usize) -> Option<(usize, usize)> {
+        match round % 3 {
+            0 => self.try_read().map(|guard| *guard),
+            _ => Some(*self.read()),
+        }
+    }
+    fn write_pair(&self, round: usize) -> bool {
+        let Some(mut guard) = (match round % 3 {
+            0 => self.try_write(),
+            _ => Some(self.write()),
+        }) else {
+            return false;
+        };
+        guard.0 += 1;
+        guard.1 += 1;
+        if round % 3 == 1 {
+            // no other writer gets in before the reader has seen the pair it wrote
+            let guard = guard.downgrade();
+            assert_eq!(guard.0, guard.1);
+        }
+        true
+    }
+}
+
+fn hammer<W: WaitStrategy + Send + Sync>(lock: ShardedRWLock<(usize, usize), W>) {
+    let writes = common::hammer(&lock, ROUNDS);
+    assert_eq!(lock.into_inner(), (writes, writes));
+}
+
+#[test]
+fn park() {
+    hammer(ShardedRWLock::new((0, 0)));
+}
+
+#[test]
+fn spin() {
+    hammer(ShardedRWLock::with_strategy((0, 0), Spin::new()));
+}
+
+#[test]
+fn spin_then_yield() {
+    hammer(ShardedRWLock::with_strategy((0, 0), SpinThenYield::new()));
+}
+
+#[test]
+fn exclusion() {
+    let lock = ShardedRWLock::new(0);
+    {
+        let _first = lock.read();
+        let _second = lock.read();
+        assert!(lock.try_write().is_none());
+    }
+    let guard = lock.write();
+    assert!(lock.try_read().is_none());
+    assert!(lock.try_write().is_none());
+    assert!(format!("{:?}", lock).contains("data: <locked>"));
+    let guard = guard.downgrade();
+    assert!(lock.try_read().is_some());
+    assert!(lock.try_write().is_none());
+    drop(guard);
+    *lock.try_write().unwrap() += 1;
+    assert_eq!(lock.into_inner(), 1);
+}
+
+// a read guard released on another thread than the one that took it
+#[test]
+fn guard_dropped_elsewhere() {
+    let lock = Arc::new(ShardedRWLock::<u8>::from(1));
+    let reader = {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            let guard = lock.read();
+            thread::scope(|scope| {
+                scope.spawn(move || assert_eq!(*guard, 1));
+            });
+        })
+    };
+    reader.join().unwrap();
+    *lock.write() += 1;
+    assert_eq!(*lock.read(), 2);
+}
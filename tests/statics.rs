@@ -0,0 +1,101 @@
+// loom primitives cannot be created in const context, so there are no static locks under loom
+#![cfg(not(loom))]
+
+mod common;
+
+use common::PairLock;
+#[cfg(feature = "std")]
+use rwlock::{ExponentialBackoff, PoisonRWLock, SpinThenYield};
+use rwlock::{LockClass, Park, Policy, RWLock, Spin, WaitStrategy};
+#[cfg(feature = "std")]
+use std::thread;
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 1000;
+
+static DEFAULT: RWLock<(usize, usize)> = RWLock::new((0, 0));
+static WRITER_PREFERRED: RWLock<(usize, usize)> =
+    RWLock::with_policy((0, 0), Policy::WriterPreferred);
+#[cfg(feature = "std")]
+static FAIR: RWLock<(usize, usize), SpinThenYield> =
+    RWLock::with_policy_and_strategy((0, 0), Policy::Fair, SpinThenYield::new());
+static SPIN: RWLock<(usize, usize), Spin> = RWLock::with_strategy((0, 0), Spin::new());
+#[cfg(feature = "std")]
+static BACKOFF: RWLock<(usize, usize), ExponentialBackoff> =
+    RWLock::with_policy_and_strategy((0, 0), Policy::WriterPreferred, ExponentialBackoff::new());
+static PARK: RWLock<(usize, usize), Park> =
+    RWLock::with_policy_and_strategy((0, 0), Policy::Fair, Park::new())
+        .with_class(LockClass::new("statics::PARK").with_level(1))
+        .with_name("statics::PARK");
+#[cfg(feature = "std")]
+static POISON: PoisonRWLock<Vec<usize>> = PoisonRWLock::with_policy(Vec::new(), Policy::Fair);
+
+impl<W: WaitStrategy + Sync> PairLock for RWLock<(usize, usize), W> {
+    fn read_pair(&self, _: usize) -> Option<(usize, usize)> {
+        Some(*self.read())
+    }
+    fn write_pair(&self, _: usize) -> bool {
+        let mut guard = self.write();
+        guard.0 += 1;
+        guard.1 += 1;
+        true
+    }
+}
+
+fn hammer<W: WaitStrategy + Sync>(lock: &'static RWLock<(usize, usize), W>) {
+    let writes = common::hammer(lock, ROUNDS);
+    assert_eq!(writes, THREADS * ROUNDS / 4);
+    assert_eq!(*lock.read(), (writes, writes));
+}
+
+#[test]
+fn default() {
+    hammer(&DEFAULT);
+}
+
+#[test]
+fn writer_preferred() {
+    hammer(&WRITER_PREFERRED);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn fair() {
+    hammer(&FAIR);
+}
+
+#[test]
+fn spin() {
+    hammer(&SPIN);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn backoff() {
+    hammer(&BACKOFF);
+}
+
+#[test]
+fn park() {
+    hammer(&PARK);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn poison() {
+    let threads: Vec<_> = (0..THREADS)
+        .map(|i| {
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    POISON.write().unwrap().push(i * ROUNDS + round);
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let mut values = POISON.read().unwrap().clone();
+    values.sort_unstable();
+    assert!(values.into_iter().eq(0..THREADS * ROUNDS));
+}
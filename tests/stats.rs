@@ -0,0 +1,111 @@
+// run with `cargo test --features stats`
+#![cfg(feature = "stats")]
+
+use rwlock::{LockStats, RWLock};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn counts_acquisitions_by_kind() {
+    let lock = RWLock::new(0);
+    drop(lock.read());
+    drop(lock.read_recursive());
+    assert!(lock.try_read().is_some());
+    *lock.write() += 1;
+    let guard = lock.upgradable_read();
+    let guard = guard.upgrade().downgrade();
+    drop(guard);
+    let stats = lock.stats();
+    assert_eq!(stats.reads, 3);
+    assert_eq!(stats.writes, 1);
+    assert_eq!(stats.upgradable_reads, 1);
+    assert_eq!(stats.upgrades, 1);
+    assert_eq!(stats.read_acquire_times.count(), 4);
+    assert_eq!(stats.write_acquire_times.count(), 2);
+    // nobody had to wait
+    assert_eq!(stats.read_spins, 0);
+    assert_eq!(stats.write_spins, 0);
+}
+
+#[test]
+fn records_hold_and_wait_times() {
+    let lock = Arc::new(RWLock::new(0));
+    let guard = lock.read();
+    let writer = {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            *lock.write() += 1;
+            start.elapsed()
+        })
+    };
+    thread::sleep(Duration::from_millis(20));
+    drop(guard);
+    let waited = writer.join().unwrap();
+
+    let stats = lock.stats();
+    assert!(stats.max_read_hold >= Duration::from_millis(20));
+    assert!(stats.max_write_hold < waited);
+    assert!(stats.write_spins > 0);
+    assert!(stats.write_wait >= Duration::from_millis(10));
+    assert!(stats.write_wait <= waited);
+    // the wait falls into one of the buckets from 8ms up
+    let slow: u64 = stats
+        .write_acquire_times
+        .buckets()
+        .filter(|&(bound, _)| bound > Duration::from_millis(8))
+        .map(|(_, count)| count)
+        .sum();
+    assert_eq!(slow, 1);
+}
+
+// the hold time of a guard is measured up to its release, on whichever thread that happens
+#[test]
+fn guard_released_on_another_thread() {
+    let lock = Arc::new(RWLock::new(0));
+    let guard = lock.write_arc();
+    thread::sleep(Duration::from_millis(50));
+    thread::spawn(move || drop(guard)).join().unwrap();
+    let guard = lock.read_arc();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+    })
+    .join()
+    .unwrap();
+
+    let stats = lock.stats();
+    assert!(stats.max_write_hold >= Duration::from_millis(50));
+    assert!(stats.max_read_hold >= Duration::from_millis(20));
+}
+
+// a look at the value is not an acquisition
+#[test]
+fn debug_is_not_counted() {
+    let lock = RWLock::new(0);
+    assert!(format!("{:?}", lock).contains("data: 0"));
+    let stats = lock.stats();
+    assert_eq!(stats.reads, 0);
+    assert_eq!(stats.read_acquire_times.count(), 0);
+}
+
+#[test]
+fn reset() {
+    let lock = RWLock::new(0);
+    let guard = lock.write();
+    thread::sleep(Duration::from_millis(1));
+    drop(guard);
+    assert_eq!(lock.stats().writes, 1);
+    lock.reset_stats();
+    let stats = lock.stats();
+    assert_eq!(stats.writes, 0);
+    assert_eq!(stats.max_write_hold, Duration::ZERO);
+    assert_eq!(stats.write_acquire_times.count(), 0);
+    assert_eq!(
+        format!("{:?}", stats),
+        format!("{:?}", LockStats::default())
+    );
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "std")]
+use rwlock::PoisonRWLock;
+use rwlock::{Policy, RWLock, Spin};
+use std::{sync::Arc, thread};
+
+#[test]
+fn owned_access() {
+    let mut lock = RWLock::new(vec![1]);
+    lock.get_mut().push(2);
+    assert_eq!(unsafe { &*lock.data_ptr() }, &[1, 2]);
+    assert_eq!(lock.into_inner(), [1, 2]);
+}
+
+#[test]
+fn replace_and_take() {
+    let lock = RWLock::new(String::from("a"));
+    assert_eq!(lock.replace(String::from("b")), "a");
+    assert_eq!(lock.take(), "b");
+    assert_eq!(*lock.read(), "");
+}
+
+#[test]
+fn swap() {
+    let a = RWLock::new(1);
+    let b = RWLock::new(2);
+    a.swap(&b);
+    assert_eq!((*a.read(), *b.read()), (2, 1));
+    // swapping a lock with itself must not deadlock
+    a.swap(&a);
+    assert_eq!(*a.read(), 2);
+}
+
+// two threads swapping the same pair the other way round
+#[test]
+fn swap_both_ways() {
+    let a = Arc::new(RWLock::new(0));
+    let b = Arc::new(RWLock::new(1));
+    let other = {
+        let (a, b) = (a.clone(), b.clone());
+        thread::spawn(move || {
+            for _ in 0..1000 {
+                b.swap(&a);
+            }
+        })
+    };
+    for _ in 0..1000 {
+        a.swap(&b);
+    }
+    other.join().unwrap();
+    assert_eq!((*a.read(), *b.read()), (0, 1));
+}
+
+#[test]
+fn default_and_from() {
+    let lock: RWLock<Vec<u8>> = RWLock::default();
+    assert!(lock.read().is_empty());
+    let lock: RWLock<u8, Spin> = 7.into();
+    assert_eq!(*lock.read(), 7);
+}
+
+#[test]
+fn debug() {
+    let lock = RWLock::new(1);
+    assert!(format!("{:?}", lock).contains("data: 1"));
+    {
+        let _reader = lock.read();
+        assert!(format!("{:?}", lock).contains("data: 1"));
+    }
+    let guard = lock.write();
+    assert_eq!(format!("{:?}", guard), "1");
+    assert!(format!("{:?}", lock).contains("data: <locked>"));
+}
+
+// a writer that is only waiting for the lock does not hide the value
+#[test]
+fn debug_with_waiting_writer() {
+    let lock = Arc::new(RWLock::with_policy(1, Policy::WriterPreferred));
+    let reader = lock.read();
+    let writer = {
+        let lock = lock.clone();
+        thread::spawn(move || *lock.write() += 1)
+    };
+    while lock.try_read().is_some() {
+        thread::yield_now();
+    }
+    assert!(format!("{:?}", lock).contains("data: 1"));
+    drop(reader);
+    writer.join().unwrap();
+    assert!(format!("{:?}", lock).contains("data: 2"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn poison() {
+    let lock = PoisonRWLock::<u8>::from(7);
+    assert!(format!("{:?}", lock).contains("data: 7"));
+    {
+        let _guard = lock.write().unwrap();
+        assert!(format!("{:?}", lock).contains("data: <locked>"));
+    }
+    assert_eq!(lock.into_inner().unwrap(), 7);
+}
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+use rwlock::{Policy, RWLock};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLICIES: [Policy; 3] = [
+    Policy::ReaderPreferred,
+    Policy::WriterPreferred,
+    Policy::Fair,
+];
+
+// an acquisition that times out gives up cleanly, so the lock is as usable as before it
+#[test]
+fn timed_out() {
+    for policy in POLICIES {
+        let lock = RWLock::with_policy(0, policy);
+        let writer = lock.write();
+        let start = Instant::now();
+        assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert!(lock.try_write_for(Duration::from_millis(10)).is_none());
+        drop(writer);
+        // no reader was left counted, nor a writer waiting or queued
+        *lock.try_write().unwrap() += 1;
+        assert!(lock.try_read().is_some());
+
+        let reader = lock.read();
+        assert!(lock.try_write_for(Duration::from_millis(10)).is_none());
+        assert!(lock.try_read().is_some(), "{:?}", policy);
+        drop(reader);
+        assert_eq!(*lock.try_write().unwrap(), 1);
+    }
+}
+
+#[test]
+fn until() {
+    let lock = RWLock::new(0);
+    let writer = lock.write();
+    let deadline = Instant::now() + Duration::from_millis(10);
+    assert!(lock.try_read_until(deadline).is_none());
+    assert!(Instant::now() >= deadline);
+    // a deadline in the past still makes a single attempt
+    assert!(lock.try_write_until(deadline).is_none());
+    drop(writer);
+    *lock.try_write_until(deadline).unwrap() += 1;
+    assert_eq!(*lock.try_read_until(deadline).unwrap(), 1);
+}
+
+// the lock is acquired in time when it is released before the deadline
+#[test]
+fn released_in_time() {
+    for policy in POLICIES {
+        let lock = Arc::new(RWLock::with_policy(0, policy));
+        let writer = lock.write();
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.try_read_for(Duration::from_secs(10)).unwrap())
+        };
+        thread::sleep(Duration::from_millis(10));
+        drop(writer);
+        assert_eq!(reader.join().unwrap(), 0);
+    }
+}
+
+// a timeout too long for a deadline to be computed waits as long as it takes
+#[test]
+fn max_timeout() {
+    let lock = Arc::new(RWLock::new(0));
+    assert!(lock.try_read_for(Duration::MAX).is_some());
+    let reader = lock.read();
+    let writer = {
+        let lock = lock.clone();
+        thread::spawn(move || *lock.try_write_for(Duration::MAX).unwrap() += 1)
+    };
+    thread::sleep(Duration::from_millis(10));
+    drop(reader);
+    writer.join().unwrap();
+    assert_eq!(*lock.try_read_for(Duration::MAX).unwrap(), 1);
+}
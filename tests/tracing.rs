@@ -0,0 +1,116 @@
+// run with `cargo test --features tracing`
+#![cfg(feature = "tracing")]
+
+use rwlock::{set_long_hold_threshold, set_slow_acquire_threshold, RWLock};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+// records the spans and events as lines of text
+#[derive(Clone, Default)]
+struct Recorder {
+    next: Arc<AtomicU64>,
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+struct Fields(String);
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0 += &format!(" {}={:?}", field.name(), value);
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut fields = Fields(format!("open {}", span.metadata().name()));
+        span.record(&mut fields);
+        self.lines.lock().unwrap().push(fields.0);
+        span::Id::from_u64(self.next.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields("event".to_owned());
+        event.record(&mut fields);
+        self.lines.lock().unwrap().push(fields.0);
+    }
+    fn enter(&self, _: &span::Id) {}
+    fn exit(&self, _: &span::Id) {}
+    fn try_close(&self, _: span::Id) -> bool {
+        self.lines.lock().unwrap().push("close".to_owned());
+        true
+    }
+}
+
+#[test]
+fn spans_and_warnings() {
+    set_slow_acquire_threshold(Duration::from_millis(5));
+    set_long_hold_threshold(Duration::from_millis(5));
+    let recorder = Recorder::default();
+    let lock = Arc::new(RWLock::new(0).with_name("cache"));
+    tracing::subscriber::with_default(recorder.clone(), || {
+        drop(lock.read());
+        let guard = lock.write();
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+    });
+    let lines = recorder.lines.lock().unwrap().clone();
+    assert_eq!(lines[0], "open read_guard lock=\"cache\"");
+    assert_eq!(lines[1], "close");
+    assert_eq!(lines[2], "open write_guard lock=\"cache\"");
+    assert!(lines[3].starts_with("event message=lock held for a long time lock=\"cache\""));
+    assert_eq!(lines[4], "close");
+    assert_eq!(lines.len(), 5);
+
+    // the warning for a slow acquisition
+    let recorder = Recorder::default();
+    let guard = lock.write();
+    let reader = {
+        let lock = lock.clone();
+        let recorder = recorder.clone();
+        thread::spawn(move || {
+            tracing::subscriber::with_default(recorder, || drop(lock.read()));
+        })
+    };
+    thread::sleep(Duration::from_millis(20));
+    drop(guard);
+    reader.join().unwrap();
+    let lines = recorder.lines.lock().unwrap().clone();
+    assert!(lines[0].starts_with("event message=slow lock acquisition lock=\"cache\""));
+    assert_eq!(lines[1], "open read_guard lock=\"cache\"");
+}
+
+// the span of a guard released on another thread than the one that took it still closes,
+// after the warning for a long hold
+#[test]
+fn guard_released_on_another_thread() {
+    set_slow_acquire_threshold(Duration::from_millis(5));
+    set_long_hold_threshold(Duration::from_millis(5));
+    let recorder = Recorder::default();
+    let lock = Arc::new(RWLock::new(0).with_name("moved"));
+    let guard = tracing::subscriber::with_default(recorder.clone(), || lock.write_arc());
+    thread::sleep(Duration::from_millis(20));
+    let releaser = {
+        let recorder = recorder.clone();
+        thread::spawn(move || tracing::subscriber::with_default(recorder, || drop(guard)))
+    };
+    releaser.join().unwrap();
+    let lines = recorder.lines.lock().unwrap().clone();
+    assert_eq!(lines[0], "open write_guard lock=\"moved\"");
+    assert!(lines[1].starts_with("event message=lock held for a long time lock=\"moved\""));
+    assert_eq!(lines[2], "close");
+    assert_eq!(lines.len(), 3);
+}
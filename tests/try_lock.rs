@@ -0,0 +1,62 @@
+#![cfg(not(loom))]
+
+use rwlock::{Policy, RWLock};
+
+const POLICIES: [Policy; 3] = [
+    Policy::ReaderPreferred,
+    Policy::WriterPreferred,
+    Policy::Fair,
+];
+
+// a failed attempt leaves nothing behind, so the lock is as usable as before it
+#[test]
+fn failed_try_read() {
+    for policy in POLICIES {
+        let lock = RWLock::with_policy(0, policy);
+        let writer = lock.write();
+        for _ in 0..3 {
+            assert!(lock.try_read().is_none());
+            assert!(lock.try_read_recursive().is_none());
+        }
+        drop(writer);
+        // no reader was left counted
+        *lock.try_write().unwrap() += 1;
+        assert_eq!(*lock.try_read().unwrap(), 1);
+    }
+}
+
+#[test]
+fn failed_try_write() {
+    for policy in POLICIES {
+        let lock = RWLock::with_policy(0, policy);
+        let reader = lock.read();
+        for _ in 0..3 {
+            assert!(lock.try_write().is_none());
+        }
+        // the failed writer holds nothing that keeps other readers or the upgradable reader out
+        assert!(lock.try_read().is_some());
+        assert!(lock.try_upgradable_read().is_some());
+        drop(reader);
+        *lock.try_write().unwrap() += 1;
+
+        let writer = lock.write();
+        assert!(lock.try_write().is_none());
+        assert!(lock.try_upgradable_read().is_none());
+        drop(writer);
+        assert_eq!(*lock.read(), 1);
+    }
+}
+
+#[test]
+fn try_upgradable_read() {
+    let lock = RWLock::new(0);
+    let upgradable = lock.try_upgradable_read().unwrap();
+    assert!(lock.try_upgradable_read().is_none());
+    assert!(lock.try_write().is_none());
+    // plain readers coexist with it, but keep it from upgrading
+    let reader = lock.try_read().unwrap();
+    let upgradable = upgradable.try_upgrade().unwrap_err();
+    drop(reader);
+    *upgradable.try_upgrade().unwrap() += 1;
+    assert_eq!(*lock.try_read().unwrap(), 1);
+}
@@ -0,0 +1,12 @@
+use rwlock::RWLock;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+fn main() {
+    let lock = Arc::new(RWLock::new(Rc::new(0)));
+    let guard = lock.write_arc();
+    thread::spawn(move || {
+        let _rc = Rc::clone(&guard);
+    });
+}
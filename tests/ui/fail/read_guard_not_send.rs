@@ -0,0 +1,10 @@
+use rwlock::RWLock;
+use std::cell::Cell;
+use std::thread;
+
+fn main() {
+    let lock = Box::leak(Box::new(RWLock::new(Cell::new(0))));
+    let guard = lock.read();
+    // another thread would read the `Cell` while this one may still hold the lock
+    thread::spawn(move || guard.set(1));
+}
@@ -0,0 +1,8 @@
+use rwlock::RWLock;
+use std::rc::Rc;
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    assert_send::<RWLock<Rc<u8>>>();
+}
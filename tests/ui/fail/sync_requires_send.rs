@@ -0,0 +1,9 @@
+use rwlock::RWLock;
+use std::sync::MutexGuard;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    // `MutexGuard` is `Sync` but must not be moved to another thread, which a writer could do
+    assert_sync::<RWLock<MutexGuard<'static, u8>>>();
+}
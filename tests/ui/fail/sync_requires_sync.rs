@@ -0,0 +1,8 @@
+use rwlock::RWLock;
+use std::cell::Cell;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<RWLock<Cell<u8>>>();
+}
@@ -0,0 +1,11 @@
+use rwlock::RWLock;
+use std::rc::Rc;
+use std::thread;
+
+fn main() {
+    let lock = Box::leak(Box::new(RWLock::new(Rc::new(0))));
+    let guard = lock.write();
+    thread::spawn(move || {
+        let _rc = Rc::clone(&guard);
+    });
+}
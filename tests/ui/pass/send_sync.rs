@@ -0,0 +1,24 @@
+use rwlock::{
+    ArcLockGuard, ArcReadOnlyGuard, LockGuard, MappedLockGuard, MappedReadOnlyGuard, RWLock,
+    ReadOnlyGuard, UpgradableReadGuard,
+};
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_send::<RWLock<Vec<u8>>>();
+    assert_sync::<RWLock<Vec<u8>>>();
+    // `Cell` may be sent to the writer, it just cannot be read from many threads at once
+    assert_send::<RWLock<std::cell::Cell<u8>>>();
+
+    assert_send::<ReadOnlyGuard<'static, Vec<u8>>>();
+    assert_sync::<ReadOnlyGuard<'static, Vec<u8>>>();
+    assert_send::<LockGuard<'static, Vec<u8>>>();
+    assert_sync::<LockGuard<'static, Vec<u8>>>();
+    assert_send::<UpgradableReadGuard<'static, Vec<u8>>>();
+    assert_send::<MappedReadOnlyGuard<'static, u8>>();
+    assert_send::<MappedLockGuard<'static, u8>>();
+    assert_send::<ArcReadOnlyGuard<Vec<u8>>>();
+    assert_send::<ArcLockGuard<Vec<u8>>>();
+}